@@ -0,0 +1,80 @@
+// Fixtures and timing shared by the benchmarks. `criterion` is not used so
+// that benchmarks build without extra dependencies.
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use bencode_encoder::Type;
+
+
+const ROUNDS: usize = 5;
+
+pub fn dht_packet() -> Type {
+    let args = BTreeMap::from([
+        (b"id".to_vec(), Type::ByteString(vec![0xab; 20])),
+        (b"target".to_vec(), Type::ByteString(vec![0xcd; 20])),
+    ]);
+    Type::Dictionary(BTreeMap::from([
+        (b"a".to_vec(), Type::Dictionary(args)),
+        (b"q".to_vec(), Type::ByteString(b"find_node".to_vec())),
+        (b"t".to_vec(), Type::ByteString(b"aa".to_vec())),
+        (b"y".to_vec(), Type::ByteString(b"q".to_vec())),
+    ]))
+}
+
+pub fn torrent() -> Type {
+    let files = (0..1000)
+        .map(|i| {
+            Type::Dictionary(BTreeMap::from([
+                (b"length".to_vec(), Type::Integer(i * 1024 * 1024)),
+                (b"path".to_vec(), Type::List(vec![
+                    Type::ByteString(b"directory".to_vec()),
+                    Type::ByteString(format!("file-{}.bin", i).into_bytes()),
+                ])),
+            ]))
+        })
+        .collect();
+    let info = Type::Dictionary(BTreeMap::from([
+        (b"files".to_vec(), Type::List(files)),
+        (b"name".to_vec(), Type::ByteString(b"benchmark".to_vec())),
+        (b"piece length".to_vec(), Type::Integer(262144)),
+        (b"pieces".to_vec(), Type::ByteString((0..20 * 4000).map(|i| i as u8).collect())),
+    ]));
+    Type::Dictionary(BTreeMap::from([
+        (b"announce".to_vec(), Type::ByteString(b"http://tracker.example.com/announce".to_vec())),
+        (b"info".to_vec(), info),
+    ]))
+}
+
+pub fn large_string() -> Type {
+    Type::ByteString(vec![0x5a; 1024 * 1024])
+}
+
+pub fn integers() -> Type {
+    Type::List((0..10_000).map(|i| Type::Integer(i * 1234567)).collect())
+}
+
+// Reports the best of several rounds, which is the least disturbed by
+// whatever else is running on the machine.
+pub fn bench<F>(name: &str, iterations: u32, mut f: F) -> Duration
+where
+    F: FnMut()
+{
+    for _ in 0..iterations / 10 {
+        f();
+    }
+
+    let per_iter = (0..ROUNDS)
+        .map(|_| {
+            let start = Instant::now();
+            for _ in 0..iterations {
+                f();
+            }
+            start.elapsed() / iterations
+        })
+        .min()
+        .unwrap();
+
+    println!("{:<28} {:>12?}/iter", name, per_iter);
+
+    per_iter
+}
@@ -0,0 +1,168 @@
+mod common;
+
+use std::collections::BTreeMap;
+use std::hint::black_box;
+
+use bencode_encoder::{Decoder, Encoder, Type};
+
+use common::{bench, dht_packet, integers, large_string, torrent};
+
+
+fn encode(t: &Type) -> Vec<u8> {
+    Encoder::encode(t).unwrap()
+}
+
+// The decoder as it was before `Parser`: recursive, reading one byte at a
+// time from an iterator and collecting digits into a buffer that is parsed
+// through a `String`. Errors are not told apart, as only the timing matters.
+fn decode_recursive<T>(bytes: &mut T) -> Option<Type>
+where
+    T: Iterator<Item = u8>
+{
+    let start_byte = bytes.next()?;
+    let t = handler(bytes, start_byte)?;
+
+    match bytes.next() {
+        None => Some(t),
+        Some(_) => None,
+    }
+}
+
+fn handler<T>(bytes: &mut T, start_byte: u8) -> Option<Type>
+where
+    T: Iterator<Item = u8>
+{
+    match start_byte {
+        b'i' => decode_integer(bytes),
+        b'l' => decode_list(bytes),
+        b'd' => decode_dictionary(bytes),
+        b'0'..=b'9' => decode_binarystring(bytes, start_byte).map(Type::ByteString),
+        _ => None,
+    }
+}
+
+fn decode_integer<T>(bytes: &mut T) -> Option<Type>
+where
+    T: Iterator<Item = u8>
+{
+    let mut buff = vec![];
+    let mut sign = 1;
+
+    match bytes.next()? {
+        b'-' => sign = -1,
+        ch @ b'0'..=b'9' => buff.push(ch),
+        _ => return None,
+    }
+
+    for ch in bytes.by_ref() {
+        match ch {
+            b'0'..=b'9' => buff.push(ch),
+            b'e' => break,
+            _ => return None,
+        }
+    }
+
+    if buff.len() > 1 && buff[0] == b'0' {
+        return None;
+    }
+
+    let i = bytes_to_int(buff)?;
+
+    if sign == -1 && i == 0 {
+        return None;
+    }
+
+    Some(Type::Integer(sign * i))
+}
+
+fn decode_binarystring<T>(bytes: &mut T, start_byte: u8) -> Option<Vec<u8>>
+where
+    T: Iterator<Item = u8>
+{
+    let mut len_buff = vec![start_byte];
+    let mut str_buff = vec![];
+
+    for ch in bytes.by_ref() {
+        match ch {
+            b'0'..=b'9' => len_buff.push(ch),
+            b':' => break,
+            _ => return None,
+        }
+    }
+
+    let len = bytes_to_int(len_buff)?;
+
+    for _ in 0..len {
+        str_buff.push(bytes.next()?);
+    }
+
+    Some(str_buff)
+}
+
+fn decode_list<T>(bytes: &mut T) -> Option<Type>
+where
+    T: Iterator<Item = u8>
+{
+    let mut l = vec![];
+
+    loop {
+        match bytes.next()? {
+            b'e' => break,
+            ch => l.push(handler(bytes, ch)?),
+        }
+    }
+
+    Some(Type::List(l))
+}
+
+fn decode_dictionary<T>(bytes: &mut T) -> Option<Type>
+where
+    T: Iterator<Item = u8>
+{
+    let mut d = BTreeMap::new();
+    let mut last_key = None;
+
+    loop {
+        match (bytes.next()?, last_key.take()) {
+            (b'e', None) => break,
+            (ch, None) => last_key = Some(decode_binarystring(bytes, ch)?),
+            (ch, Some(key)) => {
+                d.insert(key, handler(bytes, ch)?);
+            },
+        }
+    }
+
+    Some(Type::Dictionary(d))
+}
+
+fn bytes_to_int(bytes: Vec<u8>) -> Option<i64> {
+    bytes.iter().map(|&b| b as char).collect::<String>().parse::<i64>().ok()
+}
+
+fn compare(name: &str, bytes: &[u8], iterations: u32) {
+    assert_eq!(decode_recursive(&mut bytes.iter().copied()), Some(Decoder::decode_slice(bytes).unwrap()));
+
+    let recursive = bench(&format!("{} (recursive)", name), iterations, || {
+        black_box(decode_recursive(&mut black_box(bytes).iter().copied()).unwrap());
+    });
+    let iter = bench(&format!("{} (iterator)", name), iterations, || {
+        black_box(Decoder::decode(&mut black_box(bytes).iter().copied()).unwrap());
+    });
+    let slice = bench(&format!("{} (slice)", name), iterations, || {
+        black_box(Decoder::decode_slice(black_box(bytes)).unwrap());
+    });
+    let borrowed = bench(&format!("{} (ref)", name), iterations, || {
+        black_box(Decoder::decode_ref(black_box(bytes)).unwrap());
+    });
+
+    println!("{:<28} {:>11.2}x", "speedup (iterator)", recursive.as_secs_f64() / iter.as_secs_f64());
+    println!("{:<28} {:>11.2}x", "speedup (slice)", recursive.as_secs_f64() / slice.as_secs_f64());
+    println!("{:<28} {:>11.2}x\n", "speedup (ref)", recursive.as_secs_f64() / borrowed.as_secs_f64());
+}
+
+fn main() {
+    compare("dht packet", &encode(&dht_packet()), 200_000);
+    compare("torrent", &encode(&torrent()), 200);
+    compare("1 MB byte string", &encode(&large_string()), 200);
+    compare("10k integers", &encode(&integers()), 200);
+}
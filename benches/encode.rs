@@ -0,0 +1,76 @@
+mod common;
+
+use std::collections::BTreeMap;
+use std::hint::black_box;
+
+use bencode_encoder::{Encoder, Type};
+
+use common::{bench, dht_packet, integers, large_string, torrent};
+
+
+// The encoder as it was before `encode_into`: every node returns its own
+// `Vec`, which is then copied into its parent's.
+fn encode_per_node(t: &Type) -> Vec<u8> {
+    match t {
+        Type::Integer(i) => {
+            let mut bytes = vec![b'i'];
+            bytes.extend(i.to_string().bytes());
+            bytes.push(b'e');
+            bytes
+        },
+        Type::ByteString(s) => encode_bytestring_per_node(s),
+        Type::List(l) => {
+            let mut bytes = vec![b'l'];
+            for t in l {
+                bytes.extend(encode_per_node(t));
+            }
+            bytes.push(b'e');
+            bytes
+        },
+        Type::Dictionary(d) => encode_dictionary_per_node(d),
+    }
+}
+
+fn encode_bytestring_per_node(s: &[u8]) -> Vec<u8> {
+    let mut bytes = s.len().to_string().into_bytes();
+    bytes.push(b':');
+    bytes.extend_from_slice(s);
+    bytes
+}
+
+fn encode_dictionary_per_node(d: &BTreeMap<Vec<u8>, Type>) -> Vec<u8> {
+    let mut bytes = vec![b'd'];
+    for (k, v) in d {
+        bytes.extend(encode_bytestring_per_node(k));
+        bytes.extend(encode_per_node(v));
+    }
+    bytes.push(b'e');
+    bytes
+}
+
+fn compare(name: &str, t: &Type, iterations: u32) {
+    assert_eq!(encode_per_node(t), Encoder::encode(t).unwrap());
+
+    let per_node = bench(&format!("{} (per node)", name), iterations, || {
+        black_box(encode_per_node(black_box(t)));
+    });
+    let single = bench(&format!("{} (encode)", name), iterations, || {
+        black_box(Encoder::encode(black_box(t)).unwrap());
+    });
+    // Reusing one buffer leaves no allocation at all once it has grown.
+    let mut buf = vec![];
+    bench(&format!("{} (encode_into)", name), iterations, || {
+        buf.clear();
+        Encoder::encode_into(black_box(t), &mut buf).unwrap();
+        black_box(&buf);
+    });
+
+    println!("{:<28} {:>11.2}x\n", "speedup", per_node.as_secs_f64() / single.as_secs_f64());
+}
+
+fn main() {
+    compare("dht packet", &dht_packet(), 200_000);
+    compare("torrent", &torrent(), 500);
+    compare("1 MB byte string", &large_string(), 500);
+    compare("10k integers", &integers(), 500);
+}
@@ -1,17 +1,26 @@
-use bencode_encoder::Decoder;
+use std::fs::File;
 
+use bencode_encoder::{Decoder, DecodeOptions};
 
+
+// Only canonical input is accepted, so that encoding the .json file gives
+// back the original bytes and the torrent keeps its infohash.
 fn main() {
     let input_file = std::env::args().nth(1)
-            .expect("Usage: parse <input_file> <output_file>");
+            .expect("Usage: decode <input_file> <output_file> (input must be canonical bencode)");
     let output_file = std::env::args().nth(2)
-            .expect("Usage: parse <input_file> <output_file>");
+            .expect("Usage: decode <input_file> <output_file> (input must be canonical bencode)");
+
+    let file = match File::open(input_file) {
+        Err(err) => return println!("{}", err),
+        Ok(file) => file,
+    };
 
-    match Decoder::decode_from(input_file) {
-        Err(err) => println!("{}", err.to_string()),
+    match Decoder::decode_reader_with(file, &DecodeOptions::strict()) {
+        Err(err) => println!("{} (only canonical bencode can be round-tripped exactly)", err),
         Ok(t) => {
             match t.save_to_json(output_file) {
-                Err(err) => println!("{}", err.to_string()),
+                Err(err) => println!("{}", err),
                 Ok(_) => println!("Decoded bencode saved to .json file"),
             }
         },
@@ -8,10 +8,10 @@ fn main() {
             .expect("Usage: parse <input_file> <output_file>");
 
     match Type::load_from_json(input_file) {
-        Err(err) => println!("{}", err.to_string()),
+        Err(err) => println!("{}", err),
         Ok(t) => {
             match Encoder::encode_to(&t, output_file) {
-                Err(err) => println!("{}", err.to_string()),
+                Err(err) => println!("{}", err),
                 Ok(_) => println!("Encoded bencode saved to binary file"),
             }
         },
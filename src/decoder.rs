@@ -1,10 +1,22 @@
 use std::fs::File;
-use std::io::{BufReader, Read};
+use std::io::Read;
 use std::path::Path;
 
+#[cfg(feature = "tokio")]
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+#[cfg(feature = "tokio")]
+use crate::errors::{DecodeError, DecodeErrorKind};
 use crate::errors::BencodeResult;
-use crate::protocol::decode;
+use crate::options::DecodeOptions;
+use crate::protocol::{decode, decode_prefix, decode_reader, decode_slice, decode_slice_ref, decode_slice_spanned, decode_with, decode_with_warnings, StreamDecoder};
+#[cfg(feature = "tokio")]
+use crate::push::{Progress, PushParser};
+use crate::spanned::Spanned;
+use crate::stream::DecodeStream;
+use crate::type_ref::TypeRef;
 use crate::types::Type;
+use crate::warning::DecodeWarning;
 
 pub struct Decoder;
 
@@ -19,14 +31,138 @@ impl Decoder {
         }
     }
 
+    pub fn decode_with<I>(it: &mut I, options: &DecodeOptions) -> BencodeResult<Type>
+    where
+        I: Iterator<Item = u8>
+    {
+        Ok(decode_with(it, options)?)
+    }
+
+    pub fn decode_with_warnings<I>(it: &mut I, options: &DecodeOptions) -> BencodeResult<(Type, Vec<DecodeWarning>)>
+    where
+        I: Iterator<Item = u8>
+    {
+        Ok(decode_with_warnings(it, options)?)
+    }
+
+    pub fn decode_slice(bytes: &[u8]) -> BencodeResult<Type> {
+        Ok(decode_slice(bytes, &DecodeOptions::default())?)
+    }
+
+    pub fn decode_slice_with(bytes: &[u8], options: &DecodeOptions) -> BencodeResult<Type> {
+        Ok(decode_slice(bytes, options)?)
+    }
+
+    /// Decodes the value at the start of `bytes` and returns it together with
+    /// the number of bytes it took. Anything after the value is left alone,
+    /// so `&bytes[consumed..]` is the rest of the input.
+    pub fn decode_prefix(bytes: &[u8]) -> BencodeResult<(Type, usize)> {
+        Ok(decode_prefix(bytes, &DecodeOptions::default())?)
+    }
+
+    pub fn decode_prefix_with(bytes: &[u8], options: &DecodeOptions) -> BencodeResult<(Type, usize)> {
+        Ok(decode_prefix(bytes, options)?)
+    }
+
+    /// Decodes `bytes` without copying byte strings out of them.
+    pub fn decode_ref(bytes: &[u8]) -> BencodeResult<TypeRef<'_>> {
+        Ok(decode_slice_ref(bytes, &DecodeOptions::default())?)
+    }
+
+    pub fn decode_ref_with<'a>(bytes: &'a [u8], options: &DecodeOptions) -> BencodeResult<TypeRef<'a>> {
+        Ok(decode_slice_ref(bytes, options)?)
+    }
+
+    /// Decodes `bytes` into a tree that records the byte range of every
+    /// value, for pointing at exact offsets or slicing values back out.
+    pub fn decode_spanned(bytes: &[u8]) -> BencodeResult<Spanned> {
+        Ok(decode_slice_spanned(bytes, &DecodeOptions::default())?)
+    }
+
+    pub fn decode_spanned_with(bytes: &[u8], options: &DecodeOptions) -> BencodeResult<Spanned> {
+        Ok(decode_slice_spanned(bytes, options)?)
+    }
+
+    /// Decodes everything `reader` yields as a single value. Read failures
+    /// are reported as `DecodeErrorKind::Io` with the offset they happened at.
+    pub fn decode_reader<R>(reader: R) -> BencodeResult<Type>
+    where
+        R: Read
+    {
+        Ok(decode_reader(reader, &DecodeOptions::default())?)
+    }
+
+    pub fn decode_reader_with<R>(reader: R, options: &DecodeOptions) -> BencodeResult<Type>
+    where
+        R: Read
+    {
+        Ok(decode_reader(reader, options)?)
+    }
+
+    /// Reads back-to-back values from `reader`, such as a capture log of
+    /// peer messages. A value cut short by the end of the input is an error.
+    pub fn stream<R>(reader: R) -> DecodeStream<R>
+    where
+        R: Read
+    {
+        Decoder::stream_with(reader, &DecodeOptions::default())
+    }
+
+    pub fn stream_with<R>(reader: R, options: &DecodeOptions) -> DecodeStream<R>
+    where
+        R: Read
+    {
+        DecodeStream::new(StreamDecoder::new(reader, options.clone()))
+    }
+
     pub fn decode_from<P>(path: P) -> BencodeResult<Type>
     where
         P: AsRef<Path>
     {
         let file = File::open(path)?;
-        let mut reader = BufReader::new(file).bytes().map(|c| c.unwrap());
-        let decoded = decode(&mut reader)?;
 
-        Ok(decoded)
+        Decoder::decode_reader(file)
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl Decoder {
+    /// Reads a single value from `reader`. Reads are sized so that nothing
+    /// after the value is consumed and the reader can be used for whatever
+    /// follows. That means many small reads, so unbuffered sockets are best
+    /// wrapped in `tokio::io::BufReader` first.
+    pub async fn decode_async<R>(reader: &mut R) -> BencodeResult<Type>
+    where
+        R: AsyncRead + Unpin
+    {
+        Decoder::decode_async_with(reader, &DecodeOptions::default()).await
+    }
+
+    pub async fn decode_async_with<R>(reader: &mut R, options: &DecodeOptions) -> BencodeResult<Type>
+    where
+        R: AsyncRead + Unpin
+    {
+        let mut parser = PushParser::with_options(options);
+        let mut buf = vec![0; 8192];
+        let mut offset = 0;
+
+        loop {
+            let len = parser.min_remaining().min(buf.len());
+            let read = match reader.read(&mut buf[..len]).await {
+                Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(DecodeError::new(DecodeErrorKind::from(&err), offset, String::new()).into()),
+                Ok(read) => read,
+            };
+
+            if read == 0 {
+                parser.finish()?;
+                return Err(DecodeError::new(DecodeErrorKind::Empty, 0, String::new()).into());
+            }
+            offset += read;
+
+            if let Progress::Complete(t, _) = parser.feed(&buf[..read])? {
+                return Ok(t);
+            }
+        }
     }
 }
@@ -1,9 +1,13 @@
-use std::fs::OpenOptions;
 use std::io::Write;
 use std::path::Path;
 
+#[cfg(feature = "tokio")]
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
 use crate::BencodeResult;
-use crate::protocol::encode;
+use crate::options::WriteOptions;
+use crate::output::OutputFile;
+use crate::protocol::{encode, encode_into, encoded_len};
 use crate::types::Type;
 
 pub struct Encoder;
@@ -13,17 +17,56 @@ impl Encoder {
         Ok(encode(t))
     }
 
+    /// Writes the encoded value to `writer` in one pass, without building it
+    /// in memory first. Wrap unbuffered writers such as files or sockets in
+    /// a `BufWriter`.
+    pub fn encode_into<W>(t: &Type, mut writer: W) -> BencodeResult<()>
+    where
+        W: Write
+    {
+        encode_into(t, &mut writer)?;
+
+        Ok(())
+    }
+
+    /// The exact number of bytes `encode` produces for `t`.
+    pub fn encoded_len(t: &Type) -> usize {
+        encoded_len(t)
+    }
+
     pub fn encode_to<P>(t: &Type, path: P) -> BencodeResult<()>
     where
         P: AsRef<Path>
     {
-        let mut bytes = encode(t);
-        let mut file = OpenOptions::new()
-            .write(true)
-            .create_new(true)
-            .open(path)?;
-        
-        file.write_all(&mut bytes)?;
+        Encoder::encode_to_with(t, path, &WriteOptions::default())
+    }
+
+    /// Like `encode_to`, with `options` deciding what happens to an existing
+    /// file. A failed write removes any file it created.
+    pub fn encode_to_with<P>(t: &Type, path: P, options: &WriteOptions) -> BencodeResult<()>
+    where
+        P: AsRef<Path>
+    {
+        let mut file = OutputFile::create(path, options)?;
+
+        encode_into(t, &mut file)?;
+        file.commit()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl Encoder {
+    /// Writes the encoded value to `writer` and flushes it.
+    pub async fn encode_async<W>(t: &Type, writer: &mut W) -> BencodeResult<()>
+    where
+        W: AsyncWrite + Unpin
+    {
+        let bytes = encode(t);
+
+        writer.write_all(&bytes).await?;
+        writer.flush().await?;
 
         Ok(())
     }
@@ -1,6 +1,9 @@
-use std::fmt::Debug;
+use std::fmt::{Debug, Display};
 use thiserror::Error;
 
+use crate::errors::in_path;
+
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug, Error)]
 pub enum ConverterError {
     #[error("invalid string")]
@@ -11,4 +14,17 @@ pub enum ConverterError {
     InvalidList,
     #[error("invalid dictionary")]
     InvalidDictionary,
+    #[error("{message}{}", in_path(.path))]
+    Custom { message: String, path: String },
+}
+
+// Raised by `Deserialize` impls; the path is filled in by `from_value` as
+// the error passes back up through the enclosing lists and dictionaries.
+impl serde::de::Error for ConverterError {
+    fn custom<T>(msg: T) -> Self
+    where
+        T: Display
+    {
+        ConverterError::Custom { message: msg.to_string(), path: String::new() }
+    }
 }
@@ -1,9 +1,37 @@
-use std::fmt::Debug;
+use std::fmt::{Debug, Display};
+use std::io;
 use thiserror::Error;
 
+use crate::errors::in_path;
+
 
 #[derive(Debug, Error)]
-pub enum DecodeError {
+#[error("{kind} at byte {offset}{}", in_path(.path))]
+pub struct DecodeError {
+    pub kind: DecodeErrorKind,
+    pub offset: usize,
+    pub path: String,
+}
+
+impl DecodeError {
+    pub fn new(kind: DecodeErrorKind, offset: usize, path: String) -> DecodeError {
+        DecodeError { kind, offset, path }
+    }
+}
+
+// Raised by `Deserialize` impls, which know neither the offset nor the path;
+// the deserializer fills both in on the way out.
+impl serde::de::Error for DecodeError {
+    fn custom<T>(msg: T) -> Self
+    where
+        T: Display
+    {
+        DecodeError::new(DecodeErrorKind::Custom(msg.to_string()), 0, String::new())
+    }
+}
+
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum DecodeErrorKind {
     #[error("empty byte sequence")]
     Empty,
     #[error("invalid start byte")]
@@ -18,12 +46,36 @@ pub enum DecodeError {
     IntegerWithLeadingZeros,
     #[error("invalid byte string length")]
     InvalidByteStringLength,
+    #[error("byte string length with leading zeros is not allowed")]
+    ByteStringLengthWithLeadingZeros,
     #[error("invalid list")]
     InvalidList,
     #[error("invalid dictionary")]
     InvalidDictionary,
     #[error("invalid type for dictionary key")]
     InvalidDictionaryKey,
+    #[error("dictionary keys are not sorted")]
+    UnsortedDictionaryKeys,
+    #[error("duplicate dictionary key")]
+    DuplicateDictionaryKey,
     #[error("invalid byte sequence")]
     InvalidByteSequence,
+    #[error("nesting depth limit exceeded")]
+    DepthLimitExceeded,
+    #[error("byte string length limit exceeded")]
+    StringLengthLimitExceeded,
+    #[error("container item limit exceeded")]
+    ItemLimitExceeded,
+    #[error("input size limit exceeded")]
+    InputSizeLimitExceeded,
+    #[error("I/O error: {message}")]
+    Io { kind: io::ErrorKind, message: String },
+    #[error("{0}")]
+    Custom(String),
+}
+
+impl From<&io::Error> for DecodeErrorKind {
+    fn from(err: &io::Error) -> Self {
+        DecodeErrorKind::Io { kind: err.kind(), message: err.to_string() }
+    }
 }
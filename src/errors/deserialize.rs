@@ -1,11 +1,21 @@
 use std::fmt::Debug;
+use std::io;
 use thiserror::Error;
 
+use crate::errors::in_path;
 
+
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug, Error)]
 pub enum DeserializationError {
     #[error("could not be serialized to json")]
     JsonDeserializationError,
     #[error("could not open file")]
     FileError,
-}
\ No newline at end of file
+    #[error("could not read input: {0}")]
+    Io(#[from] io::Error),
+    #[error("unsupported JSON value {value}{}", in_path(.path))]
+    UnsupportedJsonValue { value: String, path: String },
+    #[error("malformed {tag} object{}", in_path(.path))]
+    InvalidJsonTag { tag: &'static str, path: String },
+}
@@ -1,12 +1,52 @@
 mod converter;
 mod decode;
 mod deserialize;
+mod ser;
 mod serialize;
+mod writer;
 
 pub use converter::ConverterError;
-pub use decode::DecodeError;
+pub use decode::{DecodeError, DecodeErrorKind};
 pub use deserialize::DeserializationError;
+pub use ser::SerError;
 pub use serialize::SerializationError;
+pub use writer::WriterError;
 
 pub type BencodeError = Box<dyn std::error::Error + Send + Sync + 'static>;
 pub type BencodeResult<T> = Result<T, BencodeError>;
+
+// One step on the way from the top-level value to the one an error is about.
+pub(crate) enum PathSegment<K> {
+    Key(K),
+    Index(usize),
+}
+
+// Renders a path such as `info.files[3].length`.
+pub(crate) fn format_path<K>(path: &[PathSegment<K>]) -> String
+where
+    K: AsRef<[u8]>
+{
+    let mut s = String::new();
+
+    for segment in path {
+        match segment {
+            PathSegment::Key(key) => {
+                if !s.is_empty() {
+                    s.push('.');
+                }
+                s.push_str(&String::from_utf8_lossy(key.as_ref()));
+            },
+            PathSegment::Index(i) => s.push_str(&format!("[{}]", i)),
+        }
+    }
+
+    s
+}
+
+// The ` in <path>` suffix of an error message, empty for the top-level value.
+pub(crate) fn in_path(path: &str) -> String {
+    match path.is_empty() {
+        true => String::new(),
+        false => format!(" in {}", path),
+    }
+}
@@ -0,0 +1,31 @@
+use std::fmt::{Debug, Display};
+use std::io;
+use thiserror::Error;
+
+
+#[derive(Debug, Error)]
+pub enum SerError {
+    #[error("could not write output: {0}")]
+    Io(#[from] io::Error),
+    #[error("{0} cannot be represented in bencode")]
+    UnsupportedType(&'static str),
+    #[error("None can only be skipped as a struct field or map value")]
+    UnsupportedNone,
+    #[error("integer out of the signed 64-bit range")]
+    IntegerOutOfRange,
+    #[error("dictionary keys must be strings, bytes or integers")]
+    InvalidKey,
+    #[error("duplicate dictionary key")]
+    DuplicateKey,
+    #[error("{0}")]
+    Custom(String),
+}
+
+impl serde::ser::Error for SerError {
+    fn custom<T>(msg: T) -> Self
+    where
+        T: Display
+    {
+        SerError::Custom(msg.to_string())
+    }
+}
@@ -1,7 +1,9 @@
 use std::fmt::Debug;
+use std::io;
 use thiserror::Error;
 
 
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug, Error)]
 pub enum SerializationError {
     #[error("could not be serialized to json")]
@@ -10,4 +12,6 @@ pub enum SerializationError {
     FileError,
     #[error("could not save to .json file")]
     FileSerializationError,
+    #[error("could not write output: {0}")]
+    Io(#[from] io::Error),
 }
\ No newline at end of file
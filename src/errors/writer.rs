@@ -0,0 +1,30 @@
+use std::fmt::Debug;
+use std::io;
+use thiserror::Error;
+
+
+#[derive(Debug, Error)]
+pub enum WriterError {
+    #[error("could not write output: {0}")]
+    Io(#[from] io::Error),
+    #[error("a complete value was already written")]
+    ValueAfterEnd,
+    #[error("key written outside of a dictionary or in place of a value")]
+    UnexpectedKey,
+    #[error("dictionary value written without a key")]
+    MissingKey,
+    #[error("dictionary closed after a key without a value")]
+    MissingValue,
+    #[error("end written with no open list or dictionary")]
+    UnexpectedEnd,
+    #[error("dictionary keys are not sorted")]
+    UnsortedDictionaryKeys,
+    #[error("duplicate dictionary key")]
+    DuplicateDictionaryKey,
+    #[error("byte string is shorter than its declared length")]
+    IncompleteByteString,
+    #[error("byte string is longer than its declared length")]
+    ByteStringTooLong,
+    #[error("output is not a complete value")]
+    Incomplete,
+}
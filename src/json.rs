@@ -0,0 +1,254 @@
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::mem;
+
+use serde::{Serialize, Serializer};
+use serde_json::{Map, Number, Value};
+
+use crate::errors::{format_path, DeserializationError, PathSegment};
+use crate::options::{JsonOptions, JsonPolicy};
+use crate::types::Type;
+
+
+// Byte strings that are not UTF-8 are written as `{"$hex": "..."}`.
+const HEX: &str = "$hex";
+// Dictionaries that cannot be written as a plain object, because a key is
+// not UTF-8 or because they would read back as a tag, are written as
+// `{"$dict": [[key, value], ...]}`.
+const DICT: &str = "$dict";
+
+/// Converts `t` to JSON. Integers become numbers, UTF-8 byte strings become
+/// strings and lists become arrays. Anything else is tagged, so that
+/// `from_json_value` gives `t` back exactly.
+pub(crate) fn to_json_value(t: &Type) -> Value {
+    match t {
+        Type::Integer(i) => Value::Number(Number::from(*i)),
+        Type::ByteString(s) => bytes_to_json(s),
+        Type::List(l) => Value::Array(l.iter().map(to_json_value).collect()),
+        Type::Dictionary(d) => {
+            let keys = d.keys().map(|k| String::from_utf8(k.clone()).ok()).collect::<Option<Vec<_>>>();
+
+            match keys {
+                Some(keys) if !looks_tagged(d) => {
+                    Value::Object(keys.into_iter().zip(d.values().map(to_json_value)).collect())
+                },
+                _ => {
+                    let pairs = d.iter().map(|(k, v)| Value::Array(vec![bytes_to_json(k), to_json_value(v)])).collect();
+                    tag(DICT, Value::Array(pairs))
+                },
+            }
+        },
+    }
+}
+
+fn bytes_to_json(bytes: &[u8]) -> Value {
+    match std::str::from_utf8(bytes) {
+        Ok(s) => Value::String(s.to_string()),
+        Err(_) => tag(HEX, Value::String(to_hex(bytes))),
+    }
+}
+
+fn tag(tag: &str, value: Value) -> Value {
+    let mut map = Map::new();
+    map.insert(tag.to_string(), value);
+
+    Value::Object(map)
+}
+
+fn looks_tagged(d: &BTreeMap<Vec<u8>, Type>) -> bool {
+    match d.keys().next() {
+        Some(k) if d.len() == 1 => k == HEX.as_bytes() || k == DICT.as_bytes(),
+        _ => false,
+    }
+}
+
+/// Serializes `t` with the mapping `Type::to_json` uses, for fields of types
+/// that derive `Serialize` and are written as JSON:
+///
+/// ```
+/// use bencode_encoder::Type;
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Response {
+///     #[serde(serialize_with = "bencode_encoder::json::tagged")]
+///     torrent: Type,
+/// }
+///
+/// let response = Response { torrent: Type::ByteString(vec![0xff]) };
+/// assert_eq!(serde_json::to_string(&response).unwrap(), r#"{"torrent":{"$hex":"ff"}}"#);
+/// ```
+///
+/// Without it, `Type`'s own `Serialize` impl writes byte strings that are
+/// not UTF-8 as one char per byte, which cannot be read back.
+pub fn tagged<S>(t: &Type, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    to_json_value(t).serialize(serializer)
+}
+
+// Unlike `serde_json::to_value(&t)`, which goes through `Type`'s `Serialize`
+// impl and writes byte strings that are not UTF-8 as one char per byte, this
+// uses the reversible mapping of `Type::to_json`. Use `tagged` to get the
+// same from `#[derive(Serialize)]`.
+impl From<&Type> for Value {
+    fn from(t: &Type) -> Self {
+        to_json_value(t)
+    }
+}
+
+impl From<Type> for Value {
+    fn from(t: Type) -> Self {
+        to_json_value(&t)
+    }
+}
+
+// Rejects values bencode has no form for, like `Type::from_json`.
+impl TryFrom<Value> for Type {
+    type Error = DeserializationError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        from_json_value(value, &JsonOptions::default())
+    }
+}
+
+/// Converts JSON produced by `to_json_value`, or written by hand, back to a
+/// `Type`. Values bencode has no form for are handled as `options` says.
+pub(crate) fn from_json_value(value: Value, options: &JsonOptions) -> Result<Type, DeserializationError> {
+    JsonReader { options, path: vec![] }.value(value)
+}
+
+struct JsonReader<'a> {
+    options: &'a JsonOptions,
+    path: Vec<PathSegment<String>>,
+}
+
+impl JsonReader<'_> {
+    fn value(&mut self, value: Value) -> Result<Type, DeserializationError> {
+        match value {
+            Value::Number(n) => self.number(n),
+            Value::String(s) => Ok(Type::ByteString(s.into_bytes())),
+            Value::Array(l) => {
+                let mut items = Vec::with_capacity(l.len());
+                for (i, t) in l.into_iter().enumerate() {
+                    self.path.push(PathSegment::Index(i));
+                    items.push(self.value(t)?);
+                    self.path.pop();
+                }
+
+                Ok(Type::List(items))
+            },
+            Value::Object(map) => self.object(map),
+            Value::Bool(b) => self.unsupported(Value::Bool(b), b as i64),
+            Value::Null => self.unsupported(Value::Null, 0),
+        }
+    }
+
+    fn number(&self, n: Number) -> Result<Type, DeserializationError> {
+        if let Some(i) = n.as_i64() {
+            return Ok(Type::Integer(i));
+        }
+
+        match (self.options.policy, n.as_f64()) {
+            (JsonPolicy::Integer, Some(f)) if n.is_f64() && f >= i64::MIN as f64 && f < i64::MAX as f64 => {
+                Ok(Type::Integer(f as i64))
+            },
+            (JsonPolicy::Integer, _) => Err(self.error(Value::Number(n))),
+            _ => self.unsupported(Value::Number(n), 0),
+        }
+    }
+
+    fn unsupported(&self, value: Value, i: i64) -> Result<Type, DeserializationError> {
+        match self.options.policy {
+            JsonPolicy::Reject => Err(self.error(value)),
+            JsonPolicy::String => Ok(Type::ByteString(value.to_string().into_bytes())),
+            JsonPolicy::Integer => Ok(Type::Integer(i)),
+        }
+    }
+
+    fn object(&mut self, mut map: Map<String, Value>) -> Result<Type, DeserializationError> {
+        if map.len() == 1 {
+            if let Some(value) = map.remove(HEX) {
+                return self.hex(value);
+            }
+            if let Some(value) = map.remove(DICT) {
+                return self.pairs(value);
+            }
+        }
+
+        let mut d = BTreeMap::new();
+        for (k, v) in map {
+            self.path.push(PathSegment::Key(k));
+            let t = self.value(v)?;
+            if let Some(PathSegment::Key(k)) = self.path.pop() {
+                d.insert(k.into_bytes(), t);
+            }
+        }
+
+        Ok(Type::Dictionary(d))
+    }
+
+    fn hex(&self, value: Value) -> Result<Type, DeserializationError> {
+        match value {
+            Value::String(s) => match from_hex(&s) {
+                Some(bytes) => Ok(Type::ByteString(bytes)),
+                None => Err(self.tag_error(HEX)),
+            },
+            _ => Err(self.tag_error(HEX)),
+        }
+    }
+
+    fn pairs(&mut self, value: Value) -> Result<Type, DeserializationError> {
+        let pairs = match value {
+            Value::Array(pairs) => pairs,
+            _ => return Err(self.tag_error(DICT)),
+        };
+
+        let mut d = BTreeMap::new();
+        for pair in pairs {
+            let (k, v) = match pair {
+                Value::Array(pair) if pair.len() == 2 => {
+                    let mut pair = pair.into_iter();
+                    (pair.next().unwrap_or_default(), pair.next().unwrap_or_default())
+                },
+                _ => return Err(self.tag_error(DICT)),
+            };
+            let key = match &mut self.value(k)? {
+                Type::ByteString(key) => mem::take(key),
+                _ => return Err(self.tag_error(DICT)),
+            };
+
+            self.path.push(PathSegment::Key(String::from_utf8_lossy(&key).into_owned()));
+            let t = self.value(v)?;
+            self.path.pop();
+            d.insert(key, t);
+        }
+
+        Ok(Type::Dictionary(d))
+    }
+
+    fn error(&self, value: Value) -> DeserializationError {
+        DeserializationError::UnsupportedJsonValue { value: value.to_string(), path: format_path(&self.path) }
+    }
+
+    fn tag_error(&self, tag: &'static str) -> DeserializationError {
+        DeserializationError::InvalidJsonTag { tag, path: format_path(&self.path) }
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[allow(clippy::manual_is_multiple_of)]
+fn from_hex(s: &str) -> Option<Vec<u8>> {
+    if s.len() % 2 != 0 || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    s.as_bytes()
+        .chunks(2)
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+        .collect()
+}
@@ -1,13 +1,34 @@
 mod decoder;
 mod encoder;
 mod errors;
+pub mod json;
+mod options;
+mod output;
 mod protocol;
+mod push;
+mod raw;
+mod ser;
+mod spanned;
+mod stream;
+mod type_ref;
 mod types;
+mod value;
+mod warning;
 
 #[cfg(test)]
 mod tests;
 
-pub use errors::{BencodeError, BencodeResult};
+pub use errors::{BencodeError, BencodeResult, ConverterError, DecodeError, DecodeErrorKind, SerError, WriterError};
 pub use decoder::Decoder;
 pub use encoder::Encoder;
+pub use options::{DecodeLimits, DecodeMode, DecodeOptions, JsonOptions, JsonPolicy, WriteMode, WriteOptions};
+pub use protocol::{from_bytes, from_bytes_with, from_reader, from_reader_with, BencodeWriter, Event, Tokenizer};
+pub use push::{Progress, PushParser};
+pub use raw::RawValue;
+pub use ser::{to_bytes, to_writer, Serializer};
+pub use spanned::{Spanned, SpannedType};
+pub use stream::DecodeStream;
+pub use type_ref::TypeRef;
 pub use types::Type;
+pub use value::{from_value, to_value};
+pub use warning::{DecodeWarning, DecodeWarningKind};
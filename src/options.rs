@@ -0,0 +1,139 @@
+/// Controls how strictly the decoder follows the bencode specification.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DecodeMode {
+    /// Rejects malformed input but tolerates non-canonical encodings such as
+    /// unsorted dictionary keys or byte string lengths with leading zeros.
+    #[default]
+    Standard,
+    /// Only accepts the canonical encoding of a value, so re-encoding the
+    /// decoded value always yields the original bytes.
+    Strict,
+    /// Accepts known deviations found in real-world files, such as unsorted
+    /// keys, leading zeros in integers or junk after the top-level value, and
+    /// reports each of them as a [`DecodeWarning`](crate::DecodeWarning).
+    Lenient,
+}
+
+/// Upper bounds on the resources a single decode may use. Exceeding any of
+/// them fails the decode with a dedicated [`DecodeErrorKind`](crate::DecodeErrorKind).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DecodeLimits {
+    /// Maximum number of nested lists and dictionaries.
+    pub max_depth: usize,
+    /// Maximum length of a single byte string, including dictionary keys.
+    pub max_string_length: usize,
+    /// Maximum number of elements in a list or entries in a dictionary.
+    pub max_items: usize,
+    /// Maximum number of bytes read from the input.
+    pub max_input_size: usize,
+}
+
+impl DecodeLimits {
+    /// No limits at all. Decoding into a `Type` and dropping or encoding it
+    /// work at any depth, but cloning, comparing or formatting one recurses
+    /// once per nesting level, as do serde conversions. `from_bytes_with`
+    /// and `from_reader_with` therefore still stop at 256 levels. Only lift
+    /// `max_depth` for trusted input or where deep values are not cloned or
+    /// compared.
+    pub fn unlimited() -> DecodeLimits {
+        DecodeLimits {
+            max_depth: usize::MAX,
+            max_string_length: usize::MAX,
+            max_items: usize::MAX,
+            max_input_size: usize::MAX,
+        }
+    }
+}
+
+// Cloning or comparing a `Type` and the serde path recurse once per level,
+// so the default depth keeps decoded values safe to handle.
+impl Default for DecodeLimits {
+    fn default() -> DecodeLimits {
+        DecodeLimits { max_depth: 256, ..DecodeLimits::unlimited() }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct DecodeOptions {
+    pub mode: DecodeMode,
+    pub limits: DecodeLimits,
+}
+
+impl DecodeOptions {
+    pub fn strict() -> DecodeOptions {
+        DecodeOptions { mode: DecodeMode::Strict, ..DecodeOptions::default() }
+    }
+
+    pub fn lenient() -> DecodeOptions {
+        DecodeOptions { mode: DecodeMode::Lenient, ..DecodeOptions::default() }
+    }
+
+    pub fn with_limits(self, limits: DecodeLimits) -> DecodeOptions {
+        DecodeOptions { limits, ..self }
+    }
+}
+
+/// What file output does when the target file already exists.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WriteMode {
+    /// Fails instead of touching an existing file.
+    #[default]
+    CreateNew,
+    /// Truncates an existing file and writes over it in place.
+    Overwrite,
+    /// Writes to a temporary file next to the target and renames it over the
+    /// target once complete, so the target is never seen half written.
+    AtomicReplace,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct WriteOptions {
+    pub mode: WriteMode,
+}
+
+impl WriteOptions {
+    pub fn create_new() -> WriteOptions {
+        WriteOptions { mode: WriteMode::CreateNew }
+    }
+
+    pub fn overwrite() -> WriteOptions {
+        WriteOptions { mode: WriteMode::Overwrite }
+    }
+
+    pub fn atomic() -> WriteOptions {
+        WriteOptions { mode: WriteMode::AtomicReplace }
+    }
+}
+
+/// What reading JSON does with values that have no bencode form: booleans,
+/// floats, `null` and integers outside the `i64` range.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum JsonPolicy {
+    /// Fails with the path of the value.
+    #[default]
+    Reject,
+    /// Writes the value's JSON text as a byte string, such as `true` or `1.5`.
+    String,
+    /// Writes `true` as 1 and `false` and `null` as 0, and truncates floats
+    /// toward zero. Numbers outside the `i64` range are still rejected.
+    Integer,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct JsonOptions {
+    pub policy: JsonPolicy,
+}
+
+impl JsonOptions {
+    pub fn reject() -> JsonOptions {
+        JsonOptions { policy: JsonPolicy::Reject }
+    }
+
+    pub fn stringify() -> JsonOptions {
+        JsonOptions { policy: JsonPolicy::String }
+    }
+
+    pub fn integer() -> JsonOptions {
+        JsonOptions { policy: JsonPolicy::Integer }
+    }
+}
@@ -0,0 +1,99 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::options::{WriteMode, WriteOptions};
+
+
+// Distinguishes temporary files created by concurrent writes in one process.
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// A file being written according to `WriteOptions`. Nothing is final until
+/// `commit`; dropping it before then removes whatever it created, so a
+/// failed write does not leave a partial file behind.
+pub struct OutputFile {
+    file: BufWriter<File>,
+    path: PathBuf,
+    // The file actually written and whether to remove it if not committed.
+    written: PathBuf,
+    remove_on_drop: bool,
+    mode: WriteMode,
+}
+
+impl OutputFile {
+    pub fn create<P>(path: P, options: &WriteOptions) -> io::Result<OutputFile>
+    where
+        P: AsRef<Path>
+    {
+        let path = path.as_ref().to_path_buf();
+
+        let (file, written, remove_on_drop) = match options.mode {
+            WriteMode::CreateNew => (OpenOptions::new().write(true).create_new(true).open(&path)?, path.clone(), true),
+            WriteMode::Overwrite => (File::create(&path)?, path.clone(), false),
+            WriteMode::AtomicReplace => {
+                let (file, temp) = create_temp(&path)?;
+                (file, temp, true)
+            },
+        };
+
+        Ok(OutputFile { file: BufWriter::new(file), path, written, remove_on_drop, mode: options.mode })
+    }
+
+    /// Flushes the output and, for atomic replace, syncs it to disk and
+    /// renames it over the target.
+    pub fn commit(mut self) -> io::Result<()> {
+        self.file.flush()?;
+
+        if self.mode == WriteMode::AtomicReplace {
+            self.file.get_ref().sync_all()?;
+            fs::rename(&self.written, &self.path)?;
+        }
+        self.remove_on_drop = false;
+
+        Ok(())
+    }
+}
+
+impl Write for OutputFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file.write(buf)
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.file.write_all(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+impl Drop for OutputFile {
+    fn drop(&mut self) {
+        if self.remove_on_drop {
+            let _ = fs::remove_file(&self.written);
+        }
+    }
+}
+
+// The temporary file goes in the target's directory, as a rename is only
+// atomic within one file system.
+fn create_temp(path: &Path) -> io::Result<(File, PathBuf)> {
+    let name = match path.file_name() {
+        Some(name) => name.to_string_lossy(),
+        None => return Err(io::Error::new(io::ErrorKind::InvalidInput, "output path has no file name")),
+    };
+
+    loop {
+        let counter = TEMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let temp = path.with_file_name(format!(".{}.{}.{}.tmp", name, process::id(), counter));
+
+        match OpenOptions::new().write(true).create_new(true).open(&temp) {
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err),
+            Ok(file) => return Ok((file, temp)),
+        }
+    }
+}
@@ -0,0 +1,799 @@
+use std::io::Read;
+use std::marker::PhantomData;
+use std::str;
+
+use serde::de::{self, DeserializeOwned, DeserializeSeed, Deserialize, IgnoredAny, Unexpected, Visitor};
+use serde::forward_to_deserialize_any;
+
+use crate::errors::{DecodeError, DecodeErrorKind, PathSegment};
+use crate::options::{DecodeMode, DecodeOptions};
+use crate::raw;
+use crate::protocol::decode::{Parser, Value};
+use crate::protocol::source::{ReaderSource, Recorder, SliceSource, TakeBytes};
+use crate::type_ref::TypeRef;
+use crate::types::Type;
+
+
+/// Deserializes a `T` from bencode, borrowing strings and bytes from `bytes`
+/// where `T` allows it.
+pub fn from_bytes<'de, T>(bytes: &'de [u8]) -> Result<T, DecodeError>
+where
+    T: Deserialize<'de>
+{
+    from_bytes_with(bytes, &DecodeOptions::default())
+}
+
+/// Like `from_bytes`, checked with `options`. Nesting deeper than 256
+/// levels is rejected even if `options` allow it, as every level takes a
+/// call into `T`'s `Deserialize` impl.
+pub fn from_bytes_with<'de, T>(bytes: &'de [u8], options: &DecodeOptions) -> Result<T, DecodeError>
+where
+    T: Deserialize<'de>
+{
+    let mut de = Deserializer::<_, TypeRef<'de>>::new(SliceSource::new(bytes), options);
+    let t = T::deserialize(&mut de)?;
+    de.end()?;
+
+    Ok(t)
+}
+
+pub fn from_reader<R, T>(reader: R) -> Result<T, DecodeError>
+where
+    R: Read,
+    T: DeserializeOwned
+{
+    from_reader_with(reader, &DecodeOptions::default())
+}
+
+/// Like `from_reader`, checked with `options`, with nesting capped at 256
+/// levels as for `from_bytes_with`.
+pub fn from_reader_with<R, T>(reader: R, options: &DecodeOptions) -> Result<T, DecodeError>
+where
+    R: Read,
+    T: DeserializeOwned
+{
+    let mut de = Deserializer::<_, Type>::new(ReaderSource::new(reader), options);
+    let t = T::deserialize(&mut de)?;
+    de.end()?;
+
+    Ok(t)
+}
+
+// Unlike the parser, `Deserialize` impls recurse once per nesting level, so
+// the serde path keeps this depth limit whatever the options say.
+const MAX_DEPTH: usize = 256;
+
+// Byte strings as the parser hands them out: borrowed from a slice or owned
+// when read from elsewhere.
+pub(crate) trait DeBytes<'de>: AsRef<[u8]> + Clone {
+    fn visit_bytes<Vis, E>(self, visitor: Vis) -> Result<Vis::Value, E>
+    where
+        Vis: Visitor<'de>,
+        E: de::Error;
+
+    // Valid UTF-8 is offered as a string, anything else as bytes.
+    fn visit_str<Vis, E>(self, visitor: Vis) -> Result<Vis::Value, E>
+    where
+        Vis: Visitor<'de>,
+        E: de::Error;
+}
+
+impl<'de> DeBytes<'de> for &'de [u8] {
+    fn visit_bytes<Vis, E>(self, visitor: Vis) -> Result<Vis::Value, E>
+    where
+        Vis: Visitor<'de>,
+        E: de::Error
+    {
+        visitor.visit_borrowed_bytes(self)
+    }
+
+    fn visit_str<Vis, E>(self, visitor: Vis) -> Result<Vis::Value, E>
+    where
+        Vis: Visitor<'de>,
+        E: de::Error
+    {
+        match str::from_utf8(self) {
+            Ok(s) => visitor.visit_borrowed_str(s),
+            Err(_) => visitor.visit_borrowed_bytes(self),
+        }
+    }
+}
+
+impl<'de> DeBytes<'de> for Vec<u8> {
+    fn visit_bytes<Vis, E>(self, visitor: Vis) -> Result<Vis::Value, E>
+    where
+        Vis: Visitor<'de>,
+        E: de::Error
+    {
+        visitor.visit_byte_buf(self)
+    }
+
+    fn visit_str<Vis, E>(self, visitor: Vis) -> Result<Vis::Value, E>
+    where
+        Vis: Visitor<'de>,
+        E: de::Error
+    {
+        match String::from_utf8(self) {
+            Ok(s) => visitor.visit_string(s),
+            Err(err) => visitor.visit_byte_buf(err.into_bytes()),
+        }
+    }
+}
+
+/// Serde deserializer reading bencode straight into `Deserialize` types,
+/// checked with the same `DecodeOptions` as `Decoder::decode_with`.
+///
+/// Byte strings are offered as strings when asked for one and valid UTF-8,
+/// and as bytes otherwise. `bool` is read from `i0e` or `i1e`, and enums
+/// from the forms the serializer writes. Errors carry the offset and path of
+/// the value they are about, including those raised by `Deserialize` impls
+/// such as missing or unknown fields.
+pub(super) struct Deserializer<S, T: Value> {
+    parser: Parser<Recorder<S>, T>,
+    // First byte of the next value when a container already had to read it.
+    pending: Option<u8>,
+    // Set once an error raised by a `Deserialize` impl got its location, so
+    // that enclosing values leave it alone on the way out.
+    located: bool,
+}
+
+impl<'de, S, T> Deserializer<S, T>
+where
+    Recorder<S>: TakeBytes<T::Bytes>,
+    T: Value,
+    T::Bytes: DeBytes<'de>
+{
+    fn new(source: S, options: &DecodeOptions) -> Self {
+        let mut options = options.clone();
+        options.limits.max_depth = options.limits.max_depth.min(MAX_DEPTH);
+
+        Deserializer { parser: Parser::new(Recorder::new(source), options), pending: None, located: false }
+    }
+
+    // Trailing bytes are only tolerated in lenient mode, as when decoding.
+    fn end(&mut self) -> Result<(), DecodeError> {
+        match self.parser.next()? {
+            Some(_) if self.parser.options.mode != DecodeMode::Lenient => Err(self.parser.error(DecodeErrorKind::InvalidByteSequence)),
+            _ => Ok(()),
+        }
+    }
+
+    // Returns the first byte of the next value and the offset it is at.
+    fn start(&mut self) -> Result<(u8, usize), DecodeError> {
+        let byte = match self.pending.take() {
+            Some(byte) => byte,
+            None => match self.parser.next()? {
+                Some(byte) => byte,
+                None => return Err(self.parser.eof_error(DecodeErrorKind::Empty)),
+            },
+        };
+
+        Ok((byte, self.parser.offset - 1))
+    }
+
+    fn locate<D>(&mut self, res: Result<D, DecodeError>, start: usize) -> Result<D, DecodeError> {
+        match res {
+            Err(err) if !self.located && matches!(err.kind, DecodeErrorKind::Custom(_)) => {
+                self.located = true;
+                Err(self.parser.error_at(err.kind, start))
+            },
+            res => res,
+        }
+    }
+
+    fn value<Vis>(&mut self, visitor: Vis, text: bool) -> Result<Vis::Value, DecodeError>
+    where
+        Vis: Visitor<'de>
+    {
+        let (byte, start) = self.start()?;
+
+        let res = match byte {
+            b'i' => {
+                let i = self.parser.decode_integer(byte)?;
+                visitor.visit_i64(i)
+            },
+            b'0'..=b'9' => {
+                let bytes = self.parser.decode_binarystring(byte)?;
+                match text {
+                    true => bytes.visit_str(visitor),
+                    false => bytes.visit_bytes(visitor),
+                }
+            },
+            b'l' => {
+                self.parser.check_depth()?;
+                let mut access = ListAccess { de: &mut *self, count: 0, done: false };
+                visitor.visit_seq(&mut access).and_then(|v| access.end().map(|_| v))
+            },
+            b'd' => {
+                self.parser.check_depth()?;
+                let mut access = DictionaryAccess { de: &mut *self, count: 0, last_key: None, done: false };
+                visitor.visit_map(&mut access).and_then(|v| access.end().map(|_| v))
+            },
+            _ => return Err(self.parser.error(DecodeErrorKind::InvalidStartByte)),
+        };
+
+        self.locate(res, start)
+    }
+
+    // Skips over the next value, checking it as usual, and hands its bytes
+    // to the visitor as they were in the input.
+    fn raw_value<Vis>(&mut self, visitor: Vis) -> Result<Vis::Value, DecodeError>
+    where
+        Vis: Visitor<'de>
+    {
+        let (byte, start) = self.start()?;
+        self.parser.source_mut().start(&[byte]);
+        self.pending = Some(byte);
+
+        let res = self.value(IgnoredAny, false);
+        let bytes = self.parser.source_mut().finish();
+        res?;
+
+        let res = visitor.visit_byte_buf(bytes);
+        self.locate(res, start)
+    }
+
+    // Reads the first byte of a value that must follow inside a dictionary.
+    fn dictionary_value(&mut self) -> Result<(), DecodeError> {
+        match self.parser.next()? {
+            None => Err(self.parser.eof_error(DecodeErrorKind::InvalidDictionary)),
+            Some(b'e') => Err(self.parser.error(DecodeErrorKind::InvalidDictionary)),
+            Some(byte) => {
+                self.pending = Some(byte);
+                Ok(())
+            },
+        }
+    }
+
+    fn dictionary_end(&mut self) -> Result<(), DecodeError> {
+        match self.parser.next()? {
+            None => Err(self.parser.eof_error(DecodeErrorKind::InvalidDictionary)),
+            Some(b'e') => Ok(()),
+            Some(_) => Err(self.parser.error(DecodeErrorKind::InvalidDictionary)),
+        }
+    }
+}
+
+impl<'de, S, T> de::Deserializer<'de> for &mut Deserializer<S, T>
+where
+    Recorder<S>: TakeBytes<T::Bytes>,
+    T: Value,
+    T::Bytes: DeBytes<'de>
+{
+    type Error = DecodeError;
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    fn deserialize_any<Vis>(self, visitor: Vis) -> Result<Vis::Value, DecodeError>
+    where
+        Vis: Visitor<'de>
+    {
+        self.value(visitor, false)
+    }
+
+    fn deserialize_bool<Vis>(self, visitor: Vis) -> Result<Vis::Value, DecodeError>
+    where
+        Vis: Visitor<'de>
+    {
+        let (byte, start) = self.start()?;
+        if byte != b'i' {
+            self.pending = Some(byte);
+            return self.value(visitor, false);
+        }
+
+        let res = match self.parser.decode_integer(byte)? {
+            0 => visitor.visit_bool(false),
+            1 => visitor.visit_bool(true),
+            i => Err(de::Error::invalid_value(Unexpected::Signed(i), &"0 or 1")),
+        };
+
+        self.locate(res, start)
+    }
+
+    fn deserialize_str<Vis>(self, visitor: Vis) -> Result<Vis::Value, DecodeError>
+    where
+        Vis: Visitor<'de>
+    {
+        self.value(visitor, true)
+    }
+
+    fn deserialize_string<Vis>(self, visitor: Vis) -> Result<Vis::Value, DecodeError>
+    where
+        Vis: Visitor<'de>
+    {
+        self.value(visitor, true)
+    }
+
+    fn deserialize_char<Vis>(self, visitor: Vis) -> Result<Vis::Value, DecodeError>
+    where
+        Vis: Visitor<'de>
+    {
+        self.value(visitor, true)
+    }
+
+    fn deserialize_identifier<Vis>(self, visitor: Vis) -> Result<Vis::Value, DecodeError>
+    where
+        Vis: Visitor<'de>
+    {
+        self.value(visitor, true)
+    }
+
+    // Missing values are left out of dictionaries rather than written as
+    // anything, so a value that is present is always `Some`.
+    fn deserialize_option<Vis>(self, visitor: Vis) -> Result<Vis::Value, DecodeError>
+    where
+        Vis: Visitor<'de>
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<Vis>(self, name: &'static str, visitor: Vis) -> Result<Vis::Value, DecodeError>
+    where
+        Vis: Visitor<'de>
+    {
+        match name {
+            raw::TOKEN => self.raw_value(visitor),
+            _ => visitor.visit_newtype_struct(self),
+        }
+    }
+
+    fn deserialize_enum<Vis>(self, _name: &'static str, _variants: &'static [&'static str], visitor: Vis) -> Result<Vis::Value, DecodeError>
+    where
+        Vis: Visitor<'de>
+    {
+        let (byte, start) = self.start()?;
+
+        let res = match byte {
+            b'0'..=b'9' => {
+                let name = self.parser.decode_binarystring(byte)?;
+                visitor.visit_enum(UnitVariant::new(name))
+            },
+            b'd' => {
+                self.parser.check_depth()?;
+                let name = match self.parser.next()? {
+                    None => return Err(self.parser.eof_error(DecodeErrorKind::InvalidDictionary)),
+                    Some(byte @ b'0'..=b'9') => self.parser.decode_binarystring(byte)?,
+                    Some(_) => return Err(self.parser.error(DecodeErrorKind::InvalidDictionaryKey)),
+                };
+                self.dictionary_value()?;
+                self.parser.path.push(PathSegment::Key(name.clone()));
+
+                let res = visitor.visit_enum(VariantAccess { de: &mut *self, name });
+                match res {
+                    Ok(v) => {
+                        self.parser.path.pop();
+                        self.dictionary_end().map(|_| v)
+                    },
+                    Err(err) => Err(err),
+                }
+            },
+            _ => {
+                self.pending = Some(byte);
+                return self.value(visitor, false);
+            },
+        };
+
+        self.locate(res, start)
+    }
+
+    forward_to_deserialize_any! {
+        i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 bytes byte_buf unit
+        unit_struct seq tuple tuple_struct map struct ignored_any
+    }
+}
+
+struct ListAccess<'a, S, T: Value> {
+    de: &'a mut Deserializer<S, T>,
+    count: usize,
+    done: bool,
+}
+
+impl<'de, S, T> ListAccess<'_, S, T>
+where
+    Recorder<S>: TakeBytes<T::Bytes>,
+    T: Value,
+    T::Bytes: DeBytes<'de>
+{
+    // A visitor may stop before the end of the list, e.g. for a tuple, in
+    // which case nothing but the end may follow.
+    fn end(&mut self) -> Result<(), DecodeError> {
+        if self.done {
+            return Ok(());
+        }
+
+        match self.de.parser.next()? {
+            Some(b'e') => Ok(()),
+            None => Err(self.de.parser.eof_error(DecodeErrorKind::InvalidList)),
+            Some(_) => Err(self.de.parser.error(DecodeErrorKind::InvalidList)),
+        }
+    }
+}
+
+impl<'de, S, T> de::SeqAccess<'de> for ListAccess<'_, S, T>
+where
+    Recorder<S>: TakeBytes<T::Bytes>,
+    T: Value,
+    T::Bytes: DeBytes<'de>
+{
+    type Error = DecodeError;
+
+    fn next_element_seed<D>(&mut self, seed: D) -> Result<Option<D::Value>, DecodeError>
+    where
+        D: DeserializeSeed<'de>
+    {
+        let parser = &mut self.de.parser;
+
+        let byte = match parser.next()? {
+            None => return Err(parser.eof_error(DecodeErrorKind::InvalidList)),
+            Some(b'e') => {
+                self.done = true;
+                return Ok(None);
+            },
+            Some(byte) => byte,
+        };
+        if self.count >= parser.options.limits.max_items {
+            return Err(parser.error(DecodeErrorKind::ItemLimitExceeded));
+        }
+        parser.path.push(PathSegment::Index(self.count));
+        self.count += 1;
+
+        self.de.pending = Some(byte);
+        let t = seed.deserialize(&mut *self.de)?;
+        self.de.parser.path.pop();
+
+        Ok(Some(t))
+    }
+}
+
+struct DictionaryAccess<'a, S, T: Value> {
+    de: &'a mut Deserializer<S, T>,
+    count: usize,
+    last_key: Option<T::Bytes>,
+    done: bool,
+}
+
+impl<'de, S, T> DictionaryAccess<'_, S, T>
+where
+    Recorder<S>: TakeBytes<T::Bytes>,
+    T: Value,
+    T::Bytes: DeBytes<'de>
+{
+    fn end(&mut self) -> Result<(), DecodeError> {
+        match self.done {
+            true => Ok(()),
+            false => self.de.dictionary_end(),
+        }
+    }
+}
+
+impl<'de, S, T> de::MapAccess<'de> for DictionaryAccess<'_, S, T>
+where
+    Recorder<S>: TakeBytes<T::Bytes>,
+    T: Value,
+    T::Bytes: DeBytes<'de>
+{
+    type Error = DecodeError;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, DecodeError>
+    where
+        K: DeserializeSeed<'de>
+    {
+        let parser = &mut self.de.parser;
+
+        let byte = match parser.next()? {
+            None => return Err(parser.eof_error(DecodeErrorKind::InvalidDictionary)),
+            Some(b'e') => {
+                self.done = true;
+                return Ok(None);
+            },
+            Some(byte) => byte,
+        };
+        if self.count >= parser.options.limits.max_items {
+            return Err(parser.error(DecodeErrorKind::ItemLimitExceeded));
+        }
+        self.count += 1;
+
+        let start = parser.offset - 1;
+        let key = match byte {
+            b'0'..=b'9' => parser.decode_binarystring(byte)?,
+            _ => return Err(parser.error(DecodeErrorKind::InvalidDictionaryKey)),
+        };
+        parser.check_key_follows(self.last_key.as_ref().map(AsRef::as_ref), key.as_ref(), start)?;
+        self.last_key = Some(key.clone());
+
+        let res = seed.deserialize(KeyDeserializer::new(key)).map(Some);
+        self.de.locate(res, start)
+    }
+
+    fn next_value_seed<D>(&mut self, seed: D) -> Result<D::Value, DecodeError>
+    where
+        D: DeserializeSeed<'de>
+    {
+        self.de.dictionary_value()?;
+        let key = match &self.last_key {
+            Some(key) => key.clone(),
+            None => unreachable!("values are only asked for after their key"),
+        };
+
+        self.de.parser.path.push(PathSegment::Key(key));
+        let t = seed.deserialize(&mut *self.de)?;
+        self.de.parser.path.pop();
+
+        Ok(t)
+    }
+}
+
+// Deserializes dictionary keys and variant names. Map keys that are
+// integers are read back from the decimal form the serializer writes.
+pub(crate) struct KeyDeserializer<B, E> {
+    key: B,
+    error: PhantomData<E>,
+}
+
+impl<'de, B, E> KeyDeserializer<B, E>
+where
+    B: DeBytes<'de>,
+    E: de::Error
+{
+    pub(crate) fn new(key: B) -> Self {
+        KeyDeserializer { key, error: PhantomData }
+    }
+
+    fn integer<Vis>(self, visitor: Vis) -> Result<Vis::Value, E>
+    where
+        Vis: Visitor<'de>
+    {
+        let i = str::from_utf8(self.key.as_ref()).ok().and_then(|s| s.parse::<i64>().ok());
+
+        match i {
+            Some(i) => visitor.visit_i64(i),
+            None => self.key.visit_bytes(visitor),
+        }
+    }
+}
+
+impl<'de, B, E> de::Deserializer<'de> for KeyDeserializer<B, E>
+where
+    B: DeBytes<'de>,
+    E: de::Error
+{
+    type Error = E;
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    fn deserialize_any<Vis>(self, visitor: Vis) -> Result<Vis::Value, E>
+    where
+        Vis: Visitor<'de>
+    {
+        self.key.visit_bytes(visitor)
+    }
+
+    fn deserialize_str<Vis>(self, visitor: Vis) -> Result<Vis::Value, E>
+    where
+        Vis: Visitor<'de>
+    {
+        self.key.visit_str(visitor)
+    }
+
+    fn deserialize_string<Vis>(self, visitor: Vis) -> Result<Vis::Value, E>
+    where
+        Vis: Visitor<'de>
+    {
+        self.key.visit_str(visitor)
+    }
+
+    fn deserialize_char<Vis>(self, visitor: Vis) -> Result<Vis::Value, E>
+    where
+        Vis: Visitor<'de>
+    {
+        self.key.visit_str(visitor)
+    }
+
+    fn deserialize_identifier<Vis>(self, visitor: Vis) -> Result<Vis::Value, E>
+    where
+        Vis: Visitor<'de>
+    {
+        self.key.visit_str(visitor)
+    }
+
+    fn deserialize_i8<Vis>(self, visitor: Vis) -> Result<Vis::Value, E>
+    where
+        Vis: Visitor<'de>
+    {
+        self.integer(visitor)
+    }
+
+    fn deserialize_i16<Vis>(self, visitor: Vis) -> Result<Vis::Value, E>
+    where
+        Vis: Visitor<'de>
+    {
+        self.integer(visitor)
+    }
+
+    fn deserialize_i32<Vis>(self, visitor: Vis) -> Result<Vis::Value, E>
+    where
+        Vis: Visitor<'de>
+    {
+        self.integer(visitor)
+    }
+
+    fn deserialize_i64<Vis>(self, visitor: Vis) -> Result<Vis::Value, E>
+    where
+        Vis: Visitor<'de>
+    {
+        self.integer(visitor)
+    }
+
+    fn deserialize_u8<Vis>(self, visitor: Vis) -> Result<Vis::Value, E>
+    where
+        Vis: Visitor<'de>
+    {
+        self.integer(visitor)
+    }
+
+    fn deserialize_u16<Vis>(self, visitor: Vis) -> Result<Vis::Value, E>
+    where
+        Vis: Visitor<'de>
+    {
+        self.integer(visitor)
+    }
+
+    fn deserialize_u32<Vis>(self, visitor: Vis) -> Result<Vis::Value, E>
+    where
+        Vis: Visitor<'de>
+    {
+        self.integer(visitor)
+    }
+
+    fn deserialize_u64<Vis>(self, visitor: Vis) -> Result<Vis::Value, E>
+    where
+        Vis: Visitor<'de>
+    {
+        self.integer(visitor)
+    }
+
+    fn deserialize_option<Vis>(self, visitor: Vis) -> Result<Vis::Value, E>
+    where
+        Vis: Visitor<'de>
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<Vis>(self, _name: &'static str, visitor: Vis) -> Result<Vis::Value, E>
+    where
+        Vis: Visitor<'de>
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    forward_to_deserialize_any! {
+        bool i128 u128 f32 f64 bytes byte_buf unit unit_struct seq tuple
+        tuple_struct map struct enum ignored_any
+    }
+}
+
+// A unit variant, written as its name alone.
+pub(crate) struct UnitVariant<B, E> {
+    name: B,
+    error: PhantomData<E>,
+}
+
+impl<B, E> UnitVariant<B, E> {
+    pub(crate) fn new(name: B) -> Self {
+        UnitVariant { name, error: PhantomData }
+    }
+}
+
+impl<'de, B, E> de::EnumAccess<'de> for UnitVariant<B, E>
+where
+    B: DeBytes<'de>,
+    E: de::Error
+{
+    type Error = E;
+    type Variant = Self;
+
+    fn variant_seed<D>(self, seed: D) -> Result<(D::Value, Self), E>
+    where
+        D: DeserializeSeed<'de>
+    {
+        let variant = seed.deserialize(KeyDeserializer::new(self.name.clone()))?;
+
+        Ok((variant, self))
+    }
+}
+
+impl<'de, B, E> de::VariantAccess<'de> for UnitVariant<B, E>
+where
+    B: DeBytes<'de>,
+    E: de::Error
+{
+    type Error = E;
+
+    fn unit_variant(self) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<D>(self, _seed: D) -> Result<D::Value, E>
+    where
+        D: DeserializeSeed<'de>
+    {
+        Err(de::Error::invalid_type(Unexpected::UnitVariant, &"newtype variant"))
+    }
+
+    fn tuple_variant<Vis>(self, _len: usize, _visitor: Vis) -> Result<Vis::Value, E>
+    where
+        Vis: Visitor<'de>
+    {
+        Err(de::Error::invalid_type(Unexpected::UnitVariant, &"tuple variant"))
+    }
+
+    fn struct_variant<Vis>(self, _fields: &'static [&'static str], _visitor: Vis) -> Result<Vis::Value, E>
+    where
+        Vis: Visitor<'de>
+    {
+        Err(de::Error::invalid_type(Unexpected::UnitVariant, &"struct variant"))
+    }
+}
+
+// Any other variant, written as a dictionary with the name as its only key.
+struct VariantAccess<'a, S, T: Value> {
+    de: &'a mut Deserializer<S, T>,
+    name: T::Bytes,
+}
+
+impl<'de, S, T> de::EnumAccess<'de> for VariantAccess<'_, S, T>
+where
+    Recorder<S>: TakeBytes<T::Bytes>,
+    T: Value,
+    T::Bytes: DeBytes<'de>
+{
+    type Error = DecodeError;
+    type Variant = Self;
+
+    fn variant_seed<D>(self, seed: D) -> Result<(D::Value, Self), DecodeError>
+    where
+        D: DeserializeSeed<'de>
+    {
+        let variant = seed.deserialize(KeyDeserializer::new(self.name.clone()))?;
+
+        Ok((variant, self))
+    }
+}
+
+impl<'de, S, T> de::VariantAccess<'de> for VariantAccess<'_, S, T>
+where
+    Recorder<S>: TakeBytes<T::Bytes>,
+    T: Value,
+    T::Bytes: DeBytes<'de>
+{
+    type Error = DecodeError;
+
+    fn unit_variant(self) -> Result<(), DecodeError> {
+        Err(de::Error::invalid_type(Unexpected::NewtypeVariant, &"unit variant"))
+    }
+
+    fn newtype_variant_seed<D>(self, seed: D) -> Result<D::Value, DecodeError>
+    where
+        D: DeserializeSeed<'de>
+    {
+        seed.deserialize(self.de)
+    }
+
+    fn tuple_variant<Vis>(self, _len: usize, visitor: Vis) -> Result<Vis::Value, DecodeError>
+    where
+        Vis: Visitor<'de>
+    {
+        de::Deserializer::deserialize_seq(self.de, visitor)
+    }
+
+    fn struct_variant<Vis>(self, _fields: &'static [&'static str], visitor: Vis) -> Result<Vis::Value, DecodeError>
+    where
+        Vis: Visitor<'de>
+    {
+        de::Deserializer::deserialize_map(self.de, visitor)
+    }
+}
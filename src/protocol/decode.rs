@@ -1,7 +1,15 @@
+use std::borrow::Borrow;
 use std::collections::BTreeMap;
+use std::io::Read;
+use std::ops::Range;
 
-use crate::errors::DecodeError;
+use crate::errors::{format_path, DecodeError, DecodeErrorKind, PathSegment};
+use crate::options::{DecodeMode, DecodeOptions};
+use crate::protocol::source::{IterSource, ReaderSource, SliceSource, TakeBytes};
+use crate::spanned::{Spanned, SpannedType};
+use crate::type_ref::TypeRef;
 use crate::types::Type;
+use crate::warning::{DecodeWarning, DecodeWarningKind};
 
 
 pub type DecodeResult = Result<Type, DecodeError>;
@@ -11,174 +19,705 @@ pub fn decode<T>(bytes: &mut T) -> DecodeResult
 where
     T: Iterator<Item = u8>
 {
-    let result = match bytes.next() {
-        None => return Err(DecodeError::Empty),
-        Some(start_byte) => handler(bytes, start_byte),
-    };
+    decode_with(bytes, &DecodeOptions::default())
+}
 
-    if result.is_err() {
-        return result;
-    }
+pub fn decode_with<T>(bytes: &mut T, options: &DecodeOptions) -> DecodeResult
+where
+    T: Iterator<Item = u8>
+{
+    let (t, _) = decode_with_warnings(bytes, options)?;
 
-    match bytes.next() {
-        None => result,
-        Some(_) => return Err(DecodeError::InvalidByteSequence),
-    }
+    Ok(t)
 }
 
-fn handler<T>(bytes: &mut T, start_byte: u8) -> DecodeResult
+pub fn decode_with_warnings<T>(bytes: &mut T, options: &DecodeOptions) -> Result<(Type, Vec<DecodeWarning>), DecodeError>
 where
     T: Iterator<Item = u8>
 {
-    match start_byte {
-        b'i' => decode_integer(bytes, start_byte),
-        b'l' => decode_list(bytes, start_byte),
-        b'd' => decode_dictionary(bytes, start_byte),
-        b'0'..=b'9' => decode_binarystring(bytes, start_byte),
-        _ => return Err(DecodeError::InvalidStartByte),
-    }
+    decode_source(IterSource::new(bytes), options)
 }
 
-fn decode_integer<T>(bytes: &mut T, _start_byte: u8) -> DecodeResult
+pub fn decode_reader<R>(reader: R, options: &DecodeOptions) -> DecodeResult
 where
-    T: Iterator<Item = u8>
+    R: Read
 {
-    let mut buff = vec![];
-    let mut sign = 1;
+    let (t, _) = decode_source(ReaderSource::new(reader), options)?;
+
+    Ok(t)
+}
+
+pub fn decode_slice(bytes: &[u8], options: &DecodeOptions) -> DecodeResult {
+    let (t, _) = decode_source(SliceSource::new(bytes), options)?;
 
-    let nxt = match bytes.next() {
-        None => return Err(DecodeError::InvalidInteger),
-        Some(ch) => ch, 
-    };
+    Ok(t)
+}
+
+pub fn decode_slice_ref<'a>(bytes: &'a [u8], options: &DecodeOptions) -> Result<TypeRef<'a>, DecodeError> {
+    let (t, _) = decode_source(SliceSource::new(bytes), options)?;
+
+    Ok(t)
+}
+
+pub fn decode_slice_spanned(bytes: &[u8], options: &DecodeOptions) -> Result<Spanned, DecodeError> {
+    let (t, _) = decode_source(SliceSource::new(bytes), options)?;
+
+    Ok(t)
+}
+
+// Decodes the value at the start of `bytes` and returns it together with
+// the number of bytes it took, leaving whatever follows alone.
+pub fn decode_prefix(bytes: &[u8], options: &DecodeOptions) -> Result<(Type, usize), DecodeError> {
+    let mut parser = Parser::new(SliceSource::new(bytes), options);
+    let t = parser.value()?;
+
+    Ok((t, parser.offset))
+}
+
+/// Decodes back-to-back values from a reader, one at a time. Limits apply
+/// to each value on its own.
+pub struct StreamDecoder<R> {
+    source: ReaderSource<R>,
+    offset: usize,
+    options: DecodeOptions,
+}
 
-    if nxt == b'-' {
-        sign = -1;
-    } else if nxt >= b'0' && nxt <= b'9' {
-        buff.push(nxt);
-    } else {
-        return Err(DecodeError::InvalidInteger);
+impl<R> StreamDecoder<R>
+where
+    R: Read
+{
+    pub fn new(reader: R, options: DecodeOptions) -> StreamDecoder<R> {
+        StreamDecoder { source: ReaderSource::new(reader), offset: 0, options }
     }
 
-    while let Some(ch) = bytes.next() {
-        match ch {
-            b'0'..=b'9' => buff.push(ch),
-            b'e' => break,
-            _ => return Err(DecodeError::InvalidEndByte("integer".to_string())),
-        }
+    /// Returns the next value and the offset it starts at, or `None` once
+    /// the reader is exhausted between two values.
+    pub fn next_value(&mut self) -> Result<Option<(usize, Type)>, DecodeError> {
+        let mut parser = Parser::new(&mut self.source, &self.options);
+        parser.base = self.offset;
+
+        let start_byte = match parser.next()? {
+            None => return Ok(None),
+            Some(start_byte) => start_byte,
+        };
+        let t = parser.handler(start_byte)?;
+
+        let offset = self.offset;
+        self.offset += parser.offset;
+
+        Ok(Some((offset, t)))
+    }
+}
+
+/// A value the parser can build, owning its byte strings or borrowing them
+/// from the input.
+pub trait Value: Sized {
+    type Bytes: AsRef<[u8]> + Ord;
+
+    fn integer(i: i64) -> Self;
+    fn byte_string(bytes: Self::Bytes) -> Self;
+    fn list(l: Vec<Self>) -> Self;
+    fn dictionary(d: BTreeMap<Self::Bytes, Self>) -> Self;
+
+    // Whether the parser tracks where values start and calls `with_span`
+    // with the input range of each one once it is complete.
+    const SPANS: bool = false;
+
+    fn with_span(self, _span: Range<usize>) -> Self {
+        self
+    }
+
+    // Called with the input range of the key a dictionary value is stored
+    // under, when `SPANS` is set.
+    fn with_key_span(self, _span: Range<usize>) -> Self {
+        self
+    }
+}
+
+impl Value for Type {
+    type Bytes = Vec<u8>;
+
+    fn integer(i: i64) -> Self {
+        Type::Integer(i)
+    }
+
+    fn byte_string(bytes: Vec<u8>) -> Self {
+        Type::ByteString(bytes)
+    }
+
+    fn list(l: Vec<Self>) -> Self {
+        Type::List(l)
     }
 
-    if buff.len() > 1 && buff[0] == b'0' {
-        return Err(DecodeError::IntegerWithLeadingZeros);
+    fn dictionary(d: BTreeMap<Vec<u8>, Self>) -> Self {
+        Type::Dictionary(d)
+    }
+}
+
+impl<'a> Value for TypeRef<'a> {
+    type Bytes = &'a [u8];
+
+    fn integer(i: i64) -> Self {
+        TypeRef::Integer(i)
     }
 
-    let i = bytes_to_int(buff)?;
+    fn byte_string(bytes: &'a [u8]) -> Self {
+        TypeRef::ByteString(bytes)
+    }
 
-    if sign == -1 && i == 0 {
-        return Err(DecodeError::NegativeZeroInteger);
+    fn list(l: Vec<Self>) -> Self {
+        TypeRef::List(l)
     }
 
-    Ok(Type::Integer(sign * i))
+    fn dictionary(d: BTreeMap<&'a [u8], Self>) -> Self {
+        TypeRef::Dictionary(d)
+    }
 }
 
-fn decode_binarystring<T>(bytes: &mut T, start_byte: u8) -> DecodeResult
+impl Value for Spanned {
+    type Bytes = Vec<u8>;
+
+    fn integer(i: i64) -> Self {
+        Spanned { span: 0..0, key_span: None, value: SpannedType::Integer(i) }
+    }
+
+    fn byte_string(bytes: Vec<u8>) -> Self {
+        Spanned { span: 0..0, key_span: None, value: SpannedType::ByteString(bytes) }
+    }
+
+    fn list(l: Vec<Self>) -> Self {
+        Spanned { span: 0..0, key_span: None, value: SpannedType::List(l) }
+    }
+
+    fn dictionary(d: BTreeMap<Vec<u8>, Self>) -> Self {
+        Spanned { span: 0..0, key_span: None, value: SpannedType::Dictionary(d) }
+    }
+
+    const SPANS: bool = true;
+
+    fn with_span(self, span: Range<usize>) -> Self {
+        Spanned { span, ..self }
+    }
+
+    fn with_key_span(self, span: Range<usize>) -> Self {
+        Spanned { key_span: Some(span), ..self }
+    }
+}
+
+fn decode_source<S, V>(source: S, options: &DecodeOptions) -> Result<(V, Vec<DecodeWarning>), DecodeError>
 where
-    T: Iterator<Item = u8>
+    S: TakeBytes<V::Bytes>,
+    V: Value
 {
-    let mut len_buff = vec![start_byte];
-    let mut str_buff = vec![];
+    let mut parser = Parser::new(source, options);
+    let result = parser.value()?;
 
-    while let Some(ch) = bytes.next() {
-        match ch {
-            b'0'..=b'9' => len_buff.push(ch),
-            b':' => break,
-            _ => return Err(DecodeError::InvalidEndByte("byte string".to_string())),
+    if parser.next()?.is_some() {
+        if options.mode != DecodeMode::Lenient {
+            return Err(parser.error(DecodeErrorKind::InvalidByteSequence));
         }
+        parser.warn(DecodeWarningKind::TrailingBytes, parser.offset - 1);
     }
 
-    let len = bytes_to_int(len_buff)?;
-    
-    for _ in 0..len {
-        match bytes.next() {
-            None => return Err(DecodeError::InvalidByteStringLength),
-            Some(ch) => str_buff.push(ch),
+    Ok((result, parser.warnings))
+}
+
+// Numbers with at most this many digits always fit in an `i64`.
+const MAX_SCAN_DIGITS: usize = 18;
+
+enum Frame<V: Value> {
+    List(Vec<V>),
+    Dictionary {
+        d: BTreeMap<V::Bytes, V>,
+        count: usize,
+        last_key: Option<V::Bytes>,
+        // Where `last_key` is in the input, when spans are tracked.
+        key_span: Range<usize>,
+    },
+}
+
+impl<V> Frame<V>
+where
+    V: Value
+{
+    fn into_value(self) -> V {
+        match self {
+            Frame::List(l) => V::list(l),
+            Frame::Dictionary { d, .. } => V::dictionary(d),
         }
     }
+}
 
-    Ok(Type::ByteString(bytes_to_str(str_buff)))
+// `offset` counts the bytes of the current value and is what the limits are
+// checked against; `base` is where that value starts in a longer stream and
+// is only added to the offsets reported in errors and warnings. One-shot
+// decodes borrow their options, while parsers that outlive the call that
+// created them own a copy.
+pub(super) struct Parser<S, V: Value, O = DecodeOptions> {
+    source: S,
+    base: usize,
+    pub(super) offset: usize,
+    pub(super) options: O,
+    pub(super) path: Vec<PathSegment<V::Bytes>>,
+    pub(super) warnings: Vec<DecodeWarning>,
 }
 
-fn decode_list<T>(bytes: &mut T, _start_byte: u8) -> DecodeResult
+impl<S, V, O> Parser<S, V, O>
 where
-    T: Iterator<Item = u8>
+    S: TakeBytes<V::Bytes>,
+    V: Value,
+    O: Borrow<DecodeOptions>
 {
-    let mut l = vec![];
+    pub(super) fn new(source: S, options: O) -> Self {
+        Parser { source, base: 0, offset: 0, options, path: vec![], warnings: vec![] }
+    }
 
-    loop {
-        match bytes.next() {
-            None => return Err(DecodeError::InvalidList),
-            Some(ch) => {
-                match ch {
-                    b'e' => break,
-                    ch => {
-                        let item = handler(bytes, ch)?;
-                        l.push(item);
+    #[inline]
+    fn options(&self) -> &DecodeOptions {
+        self.options.borrow()
+    }
+
+    pub(super) fn source_mut(&mut self) -> &mut S {
+        &mut self.source
+    }
+
+    fn value(&mut self) -> Result<V, DecodeError> {
+        match self.next()? {
+            None => Err(self.error(DecodeErrorKind::Empty)),
+            Some(start_byte) => self.handler(start_byte),
+        }
+    }
+
+    #[inline]
+    pub(super) fn next(&mut self) -> Result<Option<u8>, DecodeError> {
+        let byte = self.source.next_byte();
+        match byte {
+            Some(_) => {
+                if self.offset >= self.options().limits.max_input_size {
+                    return Err(self.eof_error(DecodeErrorKind::InputSizeLimitExceeded));
+                }
+                self.offset += 1;
+            },
+            None => self.check_io_error()?,
+        }
+
+        Ok(byte)
+    }
+
+    // A source that stopped because of an I/O error looks like EOF until
+    // the error is taken from it.
+    fn check_io_error(&mut self) -> Result<(), DecodeError> {
+        match self.source.take_error() {
+            Some(err) => Err(self.eof_error(DecodeErrorKind::from(&err))),
+            None => Ok(()),
+        }
+    }
+
+    pub(super) fn warn(&mut self, kind: DecodeWarningKind, offset: usize) {
+        self.warnings.push(DecodeWarning { kind, offset: self.base + offset });
+    }
+
+    // Errors point at the last byte read, which is the offending one, or at
+    // the end of the input when it ran out before the value was complete.
+    pub(super) fn error(&self, kind: DecodeErrorKind) -> DecodeError {
+        self.error_at(kind, self.offset.saturating_sub(1))
+    }
+
+    pub(super) fn eof_error(&self, kind: DecodeErrorKind) -> DecodeError {
+        self.error_at(kind, self.offset)
+    }
+
+    #[cold]
+    pub(super) fn error_at(&self, kind: DecodeErrorKind, offset: usize) -> DecodeError {
+        DecodeError::new(kind, self.base + offset, format_path(&self.path))
+    }
+
+    // Lists and dictionaries are kept on an explicit stack instead of the
+    // call stack, so the nesting depth is only bounded by `DecodeLimits`.
+    fn handler(&mut self, start_byte: u8) -> Result<V, DecodeError> {
+        // Neither stack allocates before its first push, so a lone integer
+        // or byte string costs nothing beyond the value itself, and `starts`
+        // is only pushed to when spans are tracked.
+        let mut stack = Vec::new();
+        // Where each container on the stack starts.
+        let mut starts = Vec::new();
+        let mut byte = start_byte;
+
+        loop {
+            let start = self.base + self.offset - 1;
+            let mut value = match byte {
+                b'i' => {
+                    let t = V::integer(self.decode_integer(byte)?);
+                    Some(self.spanned(t, start))
+                },
+                b'0'..=b'9' => {
+                    let t = V::byte_string(self.decode_binarystring(byte)?);
+                    Some(self.spanned(t, start))
+                },
+                b'l' => {
+                    self.check_depth()?;
+                    stack.push(Frame::List(vec![]));
+                    if V::SPANS {
+                        starts.push(start);
                     }
+                    None
+                },
+                b'd' => {
+                    self.check_depth()?;
+                    stack.push(Frame::Dictionary { d: BTreeMap::new(), count: 0, last_key: None, key_span: 0..0 });
+                    if V::SPANS {
+                        starts.push(start);
+                    }
+                    None
+                },
+                _ => return Err(self.error(DecodeErrorKind::InvalidStartByte)),
+            };
+
+            // Hand finished values to their parents until some container
+            // starts another item, whose first byte is decoded next.
+            byte = loop {
+                let frame = match (value.take(), stack.last_mut()) {
+                    (Some(t), None) => return Ok(t),
+                    (Some(t), Some(frame)) => {
+                        self.attach(frame, t);
+                        frame
+                    },
+                    (None, Some(frame)) => frame,
+                    (None, None) => unreachable!("containers are pushed before their items are read"),
+                };
+
+                let next = match frame {
+                    Frame::List(l) => self.decode_list(l)?,
+                    Frame::Dictionary { d, count, last_key, key_span } => self.decode_dictionary(d, count, last_key, key_span)?,
+                };
+
+                match next {
+                    Some(ch) => break ch,
+                    None => {
+                        let start = starts.pop().unwrap_or_default();
+                        value = stack.pop().map(|frame| self.spanned(frame.into_value(), start));
+                    },
                 }
+            };
+        }
+    }
+
+    #[inline]
+    fn spanned(&self, t: V, start: usize) -> V {
+        match V::SPANS {
+            true => t.with_span(start..self.base + self.offset),
+            false => t,
+        }
+    }
+
+    pub(super) fn decode_integer(&mut self, _start_byte: u8) -> Result<i64, DecodeError> {
+        if let Some(i) = self.scan_integer() {
+            return Ok(i);
+        }
+
+        let start = self.offset - 1;
+        let mut negative = false;
+        let mut digits = 0;
+        let mut leading_zero = false;
+        let mut value: Option<i64> = Some(0);
+
+        let nxt = match self.next()? {
+            None => return Err(self.eof_error(DecodeErrorKind::InvalidInteger)),
+            Some(ch) => ch,
+        };
+
+        let mut ch = match nxt {
+            b'-' => {
+                negative = true;
+                self.next()?
+            },
+            b'0'..=b'9' => Some(nxt),
+            _ => return Err(self.error(DecodeErrorKind::InvalidInteger)),
+        };
+
+        // Negative numbers are accumulated below zero so that `i64::MIN`
+        // does not overflow on its way in.
+        loop {
+            let byte = match ch {
+                None => return Err(self.eof_error(DecodeErrorKind::InvalidInteger)),
+                Some(byte) => byte,
+            };
+            match byte {
+                b'0'..=b'9' => {
+                    let digit = (byte - b'0') as i64;
+                    leading_zero |= digits == 0 && digit == 0;
+                    digits += 1;
+                    value = value.and_then(|v| v.checked_mul(10)).and_then(|v| match negative {
+                        true => v.checked_sub(digit),
+                        false => v.checked_add(digit),
+                    });
+                },
+                b'e' => break,
+                _ => return Err(self.error(DecodeErrorKind::InvalidEndByte("integer".to_string()))),
             }
+            ch = self.next()?;
         }
+
+        if leading_zero && digits > 1 {
+            if self.options().mode != DecodeMode::Lenient {
+                return Err(self.error_at(DecodeErrorKind::IntegerWithLeadingZeros, start));
+            }
+            self.warn(DecodeWarningKind::IntegerWithLeadingZeros, start);
+        }
+
+        let i = match value {
+            Some(i) if digits > 0 => i,
+            _ => return Err(self.error_at(DecodeErrorKind::InvalidInteger, start)),
+        };
+
+        if negative && i == 0 {
+            return Err(self.error_at(DecodeErrorKind::NegativeZeroInteger, start));
+        }
+
+        Ok(i)
     }
 
-    Ok(Type::List(l))
-}
+    pub(super) fn decode_binarystring(&mut self, start_byte: u8) -> Result<V::Bytes, DecodeError> {
+        let start = self.offset - 1;
 
-fn decode_dictionary<T>(bytes: &mut T, _start_byte: u8) -> DecodeResult
-where
-    T: Iterator<Item = u8>
-{
-    let mut d = BTreeMap::new();
-    let mut last_key = None;
+        if let Some(len) = self.scan_length(start_byte) {
+            if len > self.options().limits.max_string_length {
+                return Err(self.error_at(DecodeErrorKind::StringLengthLimitExceeded, start));
+            }
+            return self.read_bytes(len);
+        }
+
+        let mut digits = 1;
+        let mut len = Some((start_byte - b'0') as usize);
+
+        while let Some(ch) = self.next()? {
+            match ch {
+                b'0'..=b'9' => {
+                    digits += 1;
+                    len = len.and_then(|l| l.checked_mul(10)).and_then(|l| l.checked_add((ch - b'0') as usize));
+                },
+                b':' => break,
+                _ => return Err(self.error(DecodeErrorKind::InvalidEndByte("byte string".to_string()))),
+            }
+        }
+
+        if start_byte == b'0' && digits > 1 {
+            match self.options().mode {
+                DecodeMode::Standard => {},
+                DecodeMode::Strict => return Err(self.error_at(DecodeErrorKind::ByteStringLengthWithLeadingZeros, start)),
+                DecodeMode::Lenient => self.warn(DecodeWarningKind::ByteStringLengthWithLeadingZeros, start),
+            }
+        }
+
+        let len = match len {
+            None => return Err(self.error_at(DecodeErrorKind::StringLengthLimitExceeded, start)),
+            Some(len) => len,
+        };
+
+        if len > self.options().limits.max_string_length {
+            return Err(self.error_at(DecodeErrorKind::StringLengthLimitExceeded, start));
+        }
+
+        self.read_bytes(len)
+    }
+
+    // Parses a canonical integer straight out of in-memory input. Anything
+    // unusual is left to the byte by byte path, which also reports errors.
+    fn scan_integer(&mut self) -> Option<i64> {
+        let rest = self.source.as_slice()?;
+        let (negative, digits) = match rest.first()? {
+            b'-' => (true, &rest[1..]),
+            _ => (false, rest),
+        };
+        let len = digits.iter().position(|b| !b.is_ascii_digit())?;
+
+        if digits[len] != b'e' || len == 0 || len > MAX_SCAN_DIGITS || digits[0] == b'0' && (len > 1 || negative) {
+            return None;
+        }
+
+        let consumed = negative as usize + len + 1;
+        if consumed > self.options().limits.max_input_size - self.offset {
+            return None;
+        }
+
+        let value = digits[..len].iter().fold(0, |v, &d| v * 10 + (d - b'0') as i64);
+        self.source.skip(consumed);
+        self.offset += consumed;
+
+        Some(if negative { -value } else { value })
+    }
+
+    // Same as `scan_integer`, for the length prefix of a byte string whose
+    // first digit has already been read.
+    fn scan_length(&mut self, start_byte: u8) -> Option<usize> {
+        let rest = self.source.as_slice()?;
+        let len = rest.iter().position(|b| !b.is_ascii_digit())?;
+
+        if rest[len] != b':' || len + 1 > MAX_SCAN_DIGITS || start_byte == b'0' && len > 0 {
+            return None;
+        }
+
+        let consumed = len + 1;
+        if consumed > self.options().limits.max_input_size - self.offset {
+            return None;
+        }
+
+        // 18 digits can still overflow a 32-bit `usize`; leave those to the
+        // slow path, which reports the overflow.
+        let value = rest[..len].iter().try_fold((start_byte - b'0') as usize, |v, &d| {
+            v.checked_mul(10)?.checked_add((d - b'0') as usize)
+        })?;
+        self.source.skip(consumed);
+        self.offset += consumed;
+
+        Some(value)
+    }
 
-    loop {
-        match bytes.next() {
-            None => return Err(DecodeError::InvalidDictionary),
+    // Byte strings are copied in one go; the input size limit is checked up
+    // front instead of for every byte.
+    fn read_bytes(&mut self, len: usize) -> Result<V::Bytes, DecodeError> {
+        let allowed = len.min(self.options().limits.max_input_size - self.offset);
+
+        match self.source.take_bytes(allowed) {
+            Err(read) => {
+                self.offset += read;
+                self.check_io_error()?;
+                Err(self.eof_error(DecodeErrorKind::InvalidByteStringLength))
+            },
+            Ok(_) if allowed < len => {
+                self.offset += allowed;
+                match self.source.next_byte() {
+                    None => Err(self.eof_error(DecodeErrorKind::InvalidByteStringLength)),
+                    Some(_) => Err(self.eof_error(DecodeErrorKind::InputSizeLimitExceeded)),
+                }
+            },
+            Ok(bytes) => {
+                self.offset += len;
+                Ok(bytes)
+            },
+        }
+    }
+
+    // Reads up to the start of the next list item and returns its first
+    // byte, or `None` once the list is closed.
+    fn decode_list(&mut self, l: &[V]) -> Result<Option<u8>, DecodeError> {
+        match self.next()? {
+            None => Err(self.eof_error(DecodeErrorKind::InvalidList)),
+            Some(b'e') => Ok(None),
             Some(ch) => {
-                match ch {
-                    b'e' => break,
-                    _ => {
-                        match last_key {
-                            None => {
-                                let key = decode_binarystring(bytes, ch)?;
-                                match key {
-                                    Type::ByteString(key) => {
-                                        last_key = Some(key);
-                                    },
-                                    _ => return Err(DecodeError::InvalidDictionaryKey),
-                                };
-                            },
-                            Some(key) => {
-                                let value = handler(bytes, ch)?;
-                                d.insert(key, value);
-                                last_key = None;
-                            },
-                        }
+                if l.len() >= self.options().limits.max_items {
+                    return Err(self.error(DecodeErrorKind::ItemLimitExceeded));
+                }
+                self.path.push(PathSegment::Index(l.len()));
+                Ok(Some(ch))
+            },
+        }
+    }
+
+    // Reads the next key, if needed, up to the start of its value and
+    // returns the value's first byte, or `None` once the dictionary is closed.
+    fn decode_dictionary(
+        &mut self,
+        d: &BTreeMap<V::Bytes, V>,
+        count: &mut usize,
+        last_key: &mut Option<V::Bytes>,
+        key_span: &mut Range<usize>,
+    ) -> Result<Option<u8>, DecodeError> {
+        loop {
+            match self.next()? {
+                None => return Err(self.eof_error(DecodeErrorKind::InvalidDictionary)),
+                Some(b'e') if last_key.is_none() => return Ok(None),
+                Some(b'e') => return Err(self.error(DecodeErrorKind::InvalidDictionary)),
+                Some(ch) => {
+                    match last_key.take() {
+                        None => {
+                            if *count >= self.options().limits.max_items {
+                                return Err(self.error(DecodeErrorKind::ItemLimitExceeded));
+                            }
+                            *count += 1;
+                            let start = self.offset - 1;
+                            let key = match ch {
+                                b'0'..=b'9' => self.decode_binarystring(ch)?,
+                                _ => return Err(self.error(DecodeErrorKind::InvalidDictionaryKey)),
+                            };
+                            self.check_key_order(d, &key, start)?;
+                            if V::SPANS {
+                                *key_span = self.base + start..self.base + self.offset;
+                            }
+                            *last_key = Some(key);
+                        },
+                        Some(key) => {
+                            self.path.push(PathSegment::Key(key));
+                            return Ok(Some(ch));
+                        },
                     }
                 }
             }
         }
     }
 
-    Ok(Type::Dictionary(d))
-}
+    fn attach(&mut self, frame: &mut Frame<V>, t: V) {
+        match (frame, self.path.pop()) {
+            (Frame::List(l), _) => l.push(t),
+            (Frame::Dictionary { d, key_span, .. }, Some(PathSegment::Key(key))) => {
+                let t = match V::SPANS {
+                    true => t.with_key_span(key_span.clone()),
+                    false => t,
+                };
+                d.insert(key, t);
+            },
+            (Frame::Dictionary { .. }, _) => unreachable!("dictionary values are decoded under their key"),
+        }
+    }
 
-fn bytes_to_str(bytes: Vec<u8>) -> String { 
-    bytes.iter().map(|&b| b as char).collect::<String>()
-}
+    // The path holds one segment per enclosing container, so its length is
+    // the depth of the container that is about to be decoded, minus one.
+    pub(super) fn check_depth(&self) -> Result<(), DecodeError> {
+        match self.path.len() >= self.options().limits.max_depth {
+            true => Err(self.error(DecodeErrorKind::DepthLimitExceeded)),
+            false => Ok(()),
+        }
+    }
 
-fn bytes_to_int(bytes: Vec<u8>) -> Result<i64, DecodeError> {
-    let integer_str = bytes_to_str(bytes);
+    // Compares a key with the one before it only, for callers that do not
+    // keep the keys they have seen.
+    pub(super) fn check_key_follows(&mut self, last_key: Option<&[u8]>, key: &[u8], offset: usize) -> Result<(), DecodeError> {
+        let (error, warning) = match last_key {
+            _ if self.options().mode == DecodeMode::Standard => return Ok(()),
+            Some(last) if last == key => (DecodeErrorKind::DuplicateDictionaryKey, DecodeWarningKind::DuplicateDictionaryKey),
+            Some(last) if last > key => (DecodeErrorKind::UnsortedDictionaryKeys, DecodeWarningKind::UnsortedDictionaryKeys),
+            _ => return Ok(()),
+        };
 
-    match integer_str.parse::<i64>() {
-        Err(_) => return Err(DecodeError::InvalidInteger),
-        Ok(i) => Ok(i),
+        match self.options().mode {
+            DecodeMode::Lenient => {
+                self.warn(warning, offset);
+                Ok(())
+            },
+            _ => Err(self.error_at(error, offset)),
+        }
+    }
+
+    fn check_key_order(&mut self, d: &BTreeMap<V::Bytes, V>, key: &V::Bytes, offset: usize) -> Result<(), DecodeError> {
+        if self.options().mode == DecodeMode::Standard {
+            return Ok(());
+        }
+
+        let kind = if d.contains_key(key) {
+            DecodeWarningKind::DuplicateDictionaryKey
+        } else if d.keys().next_back().is_some_and(|last| last > key) {
+            DecodeWarningKind::UnsortedDictionaryKeys
+        } else {
+            return Ok(());
+        };
+
+        match (self.options().mode, kind) {
+            (DecodeMode::Lenient, kind) => {
+                self.warn(kind, offset);
+                Ok(())
+            },
+            (_, DecodeWarningKind::DuplicateDictionaryKey) => Err(self.error_at(DecodeErrorKind::DuplicateDictionaryKey, offset)),
+            (_, _) => Err(self.error_at(DecodeErrorKind::UnsortedDictionaryKeys, offset)),
+        }
     }
 }
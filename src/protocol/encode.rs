@@ -1,62 +1,205 @@
-use std::collections::BTreeMap;
+use std::collections::btree_map;
+use std::io::{self, Write};
+use std::slice;
 
 use crate::types::Type;
 
 
 pub fn encode(t: &Type) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(encoded_len(t));
+
+    match encode_into(t, &mut bytes) {
+        Ok(_) => bytes,
+        Err(_) => unreachable!("writing to a Vec does not fail"),
+    }
+}
+
+// Values are written by plain recursion up to this depth, which is fastest
+// for the shallow values bencode is mostly used for. Anything nested deeper
+// continues on an explicit stack, so any depth is safe.
+const MAX_RECURSION: usize = 64;
+
+// Writes `t` piece by piece without building it in memory first, so
+// unbuffered writers should be wrapped in a `BufWriter`.
+pub fn encode_into<W>(t: &Type, writer: &mut W) -> io::Result<()>
+where
+    W: Write + ?Sized
+{
+    encode_nested(t, writer, 0)
+}
+
+fn encode_nested<W>(t: &Type, writer: &mut W, depth: usize) -> io::Result<()>
+where
+    W: Write + ?Sized
+{
     match t {
-        Type::Integer(i) => encode_integer(i),
-        Type::ByteString(s) => encode_bytestring(s),
-        Type::List(l) => encode_list(l),
-        Type::Dictionary(d) => encode_dictionary(d),
+        Type::Integer(i) => encode_integer(*i, writer),
+        Type::ByteString(s) => encode_bytestring(s, writer),
+        _ if depth == MAX_RECURSION => encode_deep(t, writer),
+        Type::List(l) => {
+            writer.write_all(b"l")?;
+            for t in l {
+                encode_nested(t, writer, depth + 1)?;
+            }
+            writer.write_all(b"e")
+        },
+        Type::Dictionary(d) => {
+            writer.write_all(b"d")?;
+            for (k, v) in d {
+                encode_bytestring(k, writer)?;
+                encode_nested(v, writer, depth + 1)?;
+            }
+            writer.write_all(b"e")
+        },
     }
 }
 
-fn encode_integer(i: &i64) -> Vec<u8> {
-    let mut bytes = vec![b'i'];
+// Open containers and the children they have left to write.
+enum Frame<'a> {
+    List(slice::Iter<'a, Type>),
+    Dictionary(btree_map::Iter<'a, Vec<u8>, Type>),
+}
 
-    for byte in i.to_string().bytes() {
-        bytes.push(byte)
+fn encode_deep<W>(t: &Type, writer: &mut W) -> io::Result<()>
+where
+    W: Write + ?Sized
+{
+    let mut stack = vec![];
+    let mut t = t;
+
+    loop {
+        match t {
+            Type::Integer(i) => encode_integer(*i, writer)?,
+            Type::ByteString(s) => encode_bytestring(s, writer)?,
+            Type::List(l) => {
+                writer.write_all(b"l")?;
+                stack.push(Frame::List(l.iter()));
+            },
+            Type::Dictionary(d) => {
+                writer.write_all(b"d")?;
+                stack.push(Frame::Dictionary(d.iter()));
+            },
+        }
+
+        // Closes every container with nothing left to write, up to the next
+        // value that is due.
+        t = loop {
+            let next = match stack.last_mut() {
+                None => return Ok(()),
+                Some(Frame::List(items)) => items.next(),
+                Some(Frame::Dictionary(entries)) => match entries.next() {
+                    None => None,
+                    Some((k, v)) => {
+                        encode_bytestring(k, writer)?;
+                        Some(v)
+                    },
+                },
+            };
+
+            match next {
+                Some(next) => break next,
+                None => {
+                    writer.write_all(b"e")?;
+                    stack.pop();
+                },
+            }
+        };
     }
-    bytes.push(b'e');
+}
 
-    bytes
+// The exact number of bytes `encode` produces for `t`.
+pub fn encoded_len(t: &Type) -> usize {
+    nested_len(t, 0)
 }
 
-fn encode_bytestring(s: &String) -> Vec<u8> {
-    let mut bytes = vec![];
+fn nested_len(t: &Type, depth: usize) -> usize {
+    match t {
+        Type::Integer(i) => integer_len(*i),
+        Type::ByteString(s) => bytestring_len(s),
+        _ if depth == MAX_RECURSION => deep_len(t),
+        Type::List(l) => 2 + l.iter().map(|t| nested_len(t, depth + 1)).sum::<usize>(),
+        Type::Dictionary(d) => 2 + d.iter().map(|(k, v)| bytestring_len(k) + nested_len(v, depth + 1)).sum::<usize>(),
+    }
+}
 
-    for byte in s.len().to_string().bytes() {
-        bytes.push(byte)
+fn deep_len(t: &Type) -> usize {
+    let mut len = 0;
+    let mut stack = vec![t];
+
+    while let Some(t) = stack.pop() {
+        len += match t {
+            Type::Integer(i) => integer_len(*i),
+            Type::ByteString(s) => bytestring_len(s),
+            Type::List(l) => {
+                stack.extend(l);
+                2
+            },
+            Type::Dictionary(d) => {
+                stack.extend(d.values());
+                2 + d.keys().map(|k| bytestring_len(k)).sum::<usize>()
+            },
+        };
     }
-    bytes.push(b':');
 
-    for byte in s.to_string().bytes() {
-        bytes.push(byte)
+    len
+}
+
+pub fn encode_integer<W>(i: i64, writer: &mut W) -> io::Result<()>
+where
+    W: Write + ?Sized
+{
+    let mut buf = [0; 22];
+    buf[0] = b'i';
+    let mut len = 1;
+    if i < 0 {
+        buf[len] = b'-';
+        len += 1;
     }
+    len += format_digits(i.unsigned_abs(), &mut buf[len..]);
+    buf[len] = b'e';
 
-    bytes
+    writer.write_all(&buf[..len + 1])
 }
 
-fn encode_list(l: &Vec<Type>) -> Vec<u8> {
-    let mut bytes = vec![b'l'];
+pub fn encode_bytestring<W>(s: &[u8], writer: &mut W) -> io::Result<()>
+where
+    W: Write + ?Sized
+{
+    let mut buf = [0; 21];
+    let len = format_digits(s.len() as u64, &mut buf);
+    buf[len] = b':';
 
-    for t in l {
-        bytes.extend(encode(t));
-    }
-    bytes.push(b'e');
+    writer.write_all(&buf[..len + 1])?;
+    writer.write_all(s)
+}
 
-    bytes
+fn integer_len(i: i64) -> usize {
+    2 + (i < 0) as usize + digits(i.unsigned_abs())
 }
 
-fn encode_dictionary(d: &BTreeMap<String, Type>) -> Vec<u8> {
-    let mut bytes = vec![b'd'];
+fn bytestring_len(s: &[u8]) -> usize {
+    digits(s.len() as u64) + 1 + s.len()
+}
+
+// Writes the decimal digits of `n` to the start of `buf`, which must have
+// room for them, and returns how many there are. Going through `write!`
+// instead is several times slower for the small numbers bencode is full of.
+fn format_digits(mut n: u64, buf: &mut [u8]) -> usize {
+    let len = digits(n);
+    for byte in buf[..len].iter_mut().rev() {
+        *byte = b'0' + (n % 10) as u8;
+        n /= 10;
+    }
+
+    len
+}
 
-    for (k, v) in d {
-        bytes.extend(encode_bytestring(k));
-        bytes.extend(encode(v));
+fn digits(mut n: u64) -> usize {
+    let mut count = 1;
+    while n >= 10 {
+        n /= 10;
+        count += 1;
     }
-    bytes.push(b'e');
 
-    bytes
+    count
 }
@@ -1,6 +1,14 @@
+mod de;
 mod decode;
 mod encode;
+mod source;
+mod tokenizer;
+mod writer;
 
 
-pub use decode::{DecodeResult, decode};
-pub use encode::encode;
+pub(crate) use de::{DeBytes, KeyDeserializer, UnitVariant};
+pub use de::{from_bytes, from_bytes_with, from_reader, from_reader_with};
+pub use decode::{decode, decode_prefix, decode_reader, decode_slice, decode_slice_ref, decode_slice_spanned, decode_with, decode_with_warnings, StreamDecoder};
+pub use encode::{encode, encode_bytestring, encode_integer, encode_into, encoded_len};
+pub use tokenizer::{Event, Tokenizer};
+pub use writer::BencodeWriter;
@@ -0,0 +1,293 @@
+use std::io::{self, BufRead, BufReader, Read};
+
+/// Where the decoder reads its bytes from.
+pub trait Source {
+    fn next_byte(&mut self) -> Option<u8>;
+
+    /// Appends up to `len` bytes to `buf` and returns how many were appended,
+    /// which is less than `len` only when the input ran out.
+    fn read_into(&mut self, len: usize, buf: &mut Vec<u8>) -> usize;
+
+    /// Returns the unread input when it is already in memory.
+    fn as_slice(&self) -> Option<&[u8]> {
+        None
+    }
+
+    /// Skips `n` bytes of the slice returned by `as_slice`.
+    fn skip(&mut self, _n: usize) {}
+
+    /// Returns the I/O error that made the source stop early, if any.
+    fn take_error(&mut self) -> Option<io::Error> {
+        None
+    }
+}
+
+impl<S> Source for &mut S
+where
+    S: Source + ?Sized
+{
+    #[inline]
+    fn next_byte(&mut self) -> Option<u8> {
+        (**self).next_byte()
+    }
+
+    fn read_into(&mut self, len: usize, buf: &mut Vec<u8>) -> usize {
+        (**self).read_into(len, buf)
+    }
+
+    fn as_slice(&self) -> Option<&[u8]> {
+        (**self).as_slice()
+    }
+
+    fn skip(&mut self, n: usize) {
+        (**self).skip(n)
+    }
+
+    fn take_error(&mut self) -> Option<io::Error> {
+        (**self).take_error()
+    }
+}
+
+/// Takes byte strings out of a source, either as owned copies or as slices
+/// borrowed from the input.
+pub trait TakeBytes<B>: Source {
+    /// Takes exactly `len` bytes. When the input runs out first, whatever was
+    /// left is consumed and its length returned as the error.
+    fn take_bytes(&mut self, len: usize) -> Result<B, usize>;
+}
+
+impl<S> TakeBytes<Vec<u8>> for S
+where
+    S: Source
+{
+    fn take_bytes(&mut self, len: usize) -> Result<Vec<u8>, usize> {
+        let mut buf = vec![];
+
+        match self.read_into(len, &mut buf) {
+            read if read < len => Err(read),
+            _ => Ok(buf),
+        }
+    }
+}
+
+pub struct IterSource<'a, T> {
+    bytes: &'a mut T,
+}
+
+impl<'a, T> IterSource<'a, T>
+where
+    T: Iterator<Item = u8>
+{
+    pub fn new(bytes: &'a mut T) -> IterSource<'a, T> {
+        IterSource { bytes }
+    }
+}
+
+impl<T> Source for IterSource<'_, T>
+where
+    T: Iterator<Item = u8>
+{
+    #[inline]
+    fn next_byte(&mut self) -> Option<u8> {
+        self.bytes.next()
+    }
+
+    fn read_into(&mut self, len: usize, buf: &mut Vec<u8>) -> usize {
+        let before = buf.len();
+        buf.extend(self.bytes.by_ref().take(len));
+
+        buf.len() - before
+    }
+}
+
+// Reads through a buffer and keeps the first I/O error, which then ends the
+// input like EOF does until the parser picks it up with `take_error`.
+pub struct ReaderSource<R> {
+    reader: BufReader<R>,
+    error: Option<io::Error>,
+}
+
+impl<R> ReaderSource<R>
+where
+    R: Read
+{
+    pub fn new(reader: R) -> ReaderSource<R> {
+        ReaderSource { reader: BufReader::new(reader), error: None }
+    }
+
+    fn fill(&mut self) -> &[u8] {
+        if self.error.is_some() {
+            return &[];
+        }
+
+        loop {
+            match self.reader.fill_buf() {
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => {
+                    self.error = Some(err);
+                    return &[];
+                },
+                Ok(_) => return self.reader.buffer(),
+            }
+        }
+    }
+}
+
+impl<R> Source for ReaderSource<R>
+where
+    R: Read
+{
+    #[inline]
+    fn next_byte(&mut self) -> Option<u8> {
+        let byte = *self.fill().first()?;
+        self.reader.consume(1);
+
+        Some(byte)
+    }
+
+    fn read_into(&mut self, len: usize, buf: &mut Vec<u8>) -> usize {
+        let mut read = 0;
+
+        while read < len {
+            let chunk = self.fill();
+            if chunk.is_empty() {
+                break;
+            }
+
+            let n = chunk.len().min(len - read);
+            buf.extend_from_slice(&chunk[..n]);
+            self.reader.consume(n);
+            read += n;
+        }
+
+        read
+    }
+
+    fn take_error(&mut self) -> Option<io::Error> {
+        self.error.take()
+    }
+}
+
+// Holds the unread part of the input, so byte strings can be copied out
+// with a single `extend_from_slice`.
+pub struct SliceSource<'a> {
+    rest: std::slice::Iter<'a, u8>,
+}
+
+impl<'a> SliceSource<'a> {
+    pub fn new(bytes: &'a [u8]) -> SliceSource<'a> {
+        SliceSource { rest: bytes.iter() }
+    }
+}
+
+impl Source for SliceSource<'_> {
+    #[inline]
+    fn next_byte(&mut self) -> Option<u8> {
+        self.rest.next().copied()
+    }
+
+    fn read_into(&mut self, len: usize, buf: &mut Vec<u8>) -> usize {
+        let rest = self.rest.as_slice();
+        let (head, tail) = rest.split_at(len.min(rest.len()));
+        buf.extend_from_slice(head);
+        self.rest = tail.iter();
+
+        head.len()
+    }
+
+    fn as_slice(&self) -> Option<&[u8]> {
+        Some(self.rest.as_slice())
+    }
+
+    fn skip(&mut self, n: usize) {
+        self.rest = self.rest.as_slice()[n..].iter();
+    }
+}
+
+impl<'a> TakeBytes<&'a [u8]> for SliceSource<'a> {
+    fn take_bytes(&mut self, len: usize) -> Result<&'a [u8], usize> {
+        let rest = self.rest.as_slice();
+        if rest.len() < len {
+            self.rest = rest[rest.len()..].iter();
+            return Err(rest.len());
+        }
+
+        let (head, tail) = rest.split_at(len);
+        self.rest = tail.iter();
+
+        Ok(head)
+    }
+}
+
+// Keeps a copy of what is taken from `S` between `start` and `finish`, so the
+// exact encoding of a value can be captured while it is parsed.
+pub struct Recorder<S> {
+    source: S,
+    buf: Option<Vec<u8>>,
+}
+
+impl<S> Recorder<S> {
+    pub fn new(source: S) -> Recorder<S> {
+        Recorder { source, buf: None }
+    }
+
+    // `first` holds whatever of the value was already taken.
+    pub fn start(&mut self, first: &[u8]) {
+        self.buf = Some(first.to_vec());
+    }
+
+    pub fn finish(&mut self) -> Vec<u8> {
+        self.buf.take().unwrap_or_default()
+    }
+}
+
+impl<S> Source for Recorder<S>
+where
+    S: Source
+{
+    #[inline]
+    fn next_byte(&mut self) -> Option<u8> {
+        let byte = self.source.next_byte()?;
+        if let Some(buf) = &mut self.buf {
+            buf.push(byte);
+        }
+
+        Some(byte)
+    }
+
+    fn read_into(&mut self, len: usize, buf: &mut Vec<u8>) -> usize {
+        let before = buf.len();
+        let read = self.source.read_into(len, buf);
+        if let Some(recorded) = &mut self.buf {
+            recorded.extend_from_slice(&buf[before..]);
+        }
+
+        read
+    }
+
+    fn as_slice(&self) -> Option<&[u8]> {
+        self.source.as_slice()
+    }
+
+    fn skip(&mut self, n: usize) {
+        if let (Some(recorded), Some(rest)) = (&mut self.buf, self.source.as_slice()) {
+            recorded.extend_from_slice(&rest[..n]);
+        }
+        self.source.skip(n)
+    }
+
+    fn take_error(&mut self) -> Option<io::Error> {
+        self.source.take_error()
+    }
+}
+
+impl<'a> TakeBytes<&'a [u8]> for Recorder<SliceSource<'a>> {
+    fn take_bytes(&mut self, len: usize) -> Result<&'a [u8], usize> {
+        let bytes = self.source.take_bytes(len)?;
+        if let Some(recorded) = &mut self.buf {
+            recorded.extend_from_slice(bytes);
+        }
+
+        Ok(bytes)
+    }
+}
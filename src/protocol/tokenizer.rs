@@ -0,0 +1,176 @@
+use std::io::Read;
+
+use crate::errors::{DecodeError, DecodeErrorKind};
+use crate::options::{DecodeMode, DecodeOptions};
+use crate::errors::PathSegment;
+use crate::protocol::decode::Parser;
+use crate::protocol::source::ReaderSource;
+use crate::types::Type;
+use crate::warning::{DecodeWarning, DecodeWarningKind};
+
+
+/// A single token of a bencoded value. Every `ListStart` and `DictStart` is
+/// matched by an `End`, and inside a dictionary every `Key` is followed by
+/// the tokens of its value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    ListStart,
+    DictStart,
+    Key(Vec<u8>),
+    Integer(i64),
+    Bytes(Vec<u8>),
+    End,
+}
+
+enum Container {
+    List {
+        count: usize,
+    },
+    Dictionary {
+        count: usize,
+        last_key: Option<Vec<u8>>,
+        expects_key: bool,
+    },
+}
+
+/// Pull parser that yields the tokens of one value together with the byte
+/// offset each starts at, without building a `Type`. Memory use is bounded
+/// by the nesting depth and the longest byte string, so it suits filtering
+/// or transcoding files too large to decode at once.
+///
+/// Input is checked like `Decoder::decode_with` does, except that in lenient
+/// mode each dictionary key is only compared with the one before it.
+pub struct Tokenizer<R> {
+    parser: Parser<ReaderSource<R>, Type>,
+    stack: Vec<Container>,
+    started: bool,
+    done: bool,
+}
+
+impl<R> Tokenizer<R>
+where
+    R: Read
+{
+    pub fn new(reader: R) -> Tokenizer<R> {
+        Tokenizer::with_options(reader, &DecodeOptions::default())
+    }
+
+    pub fn with_options(reader: R, options: &DecodeOptions) -> Tokenizer<R> {
+        Tokenizer { parser: Parser::new(ReaderSource::new(reader), options.clone()), stack: vec![], started: false, done: false }
+    }
+
+    /// Deviations accepted so far in lenient mode.
+    pub fn warnings(&self) -> &[DecodeWarning] {
+        &self.parser.warnings
+    }
+
+    fn step(&mut self) -> Result<Option<(usize, Event)>, DecodeError> {
+        let byte = self.parser.next()?;
+        let offset = self.parser.offset.saturating_sub(1);
+        let limits = &self.parser.options.limits;
+
+        let byte = match (byte, self.stack.last_mut()) {
+            (None, None) if self.started => return Ok(None),
+            (None, None) => return Err(self.parser.error(DecodeErrorKind::Empty)),
+            (Some(_), None) if self.started => {
+                if self.parser.options.mode != DecodeMode::Lenient {
+                    return Err(self.parser.error(DecodeErrorKind::InvalidByteSequence));
+                }
+                self.parser.warn(DecodeWarningKind::TrailingBytes, offset);
+                return Ok(None);
+            },
+            (Some(byte), None) => {
+                self.started = true;
+                byte
+            },
+            (None, Some(Container::List { .. })) => return Err(self.parser.eof_error(DecodeErrorKind::InvalidList)),
+            (None, Some(Container::Dictionary { .. })) => return Err(self.parser.eof_error(DecodeErrorKind::InvalidDictionary)),
+            (Some(b'e'), Some(Container::List { .. })) | (Some(b'e'), Some(Container::Dictionary { expects_key: true, .. })) => {
+                self.stack.pop();
+                self.end_item();
+                return Ok(Some((offset, Event::End)));
+            },
+            (Some(b'e'), Some(Container::Dictionary { .. })) => return Err(self.parser.error(DecodeErrorKind::InvalidDictionary)),
+            (Some(byte), Some(Container::List { count })) => {
+                if *count >= limits.max_items {
+                    return Err(self.parser.error(DecodeErrorKind::ItemLimitExceeded));
+                }
+                self.parser.path.push(PathSegment::Index(*count));
+                *count += 1;
+                byte
+            },
+            (Some(byte), Some(Container::Dictionary { count, last_key, expects_key: expects_key @ true })) => {
+                if *count >= limits.max_items {
+                    return Err(self.parser.error(DecodeErrorKind::ItemLimitExceeded));
+                }
+                *count += 1;
+                let key = match byte {
+                    b'0'..=b'9' => self.parser.decode_binarystring(byte)?,
+                    _ => return Err(self.parser.error(DecodeErrorKind::InvalidDictionaryKey)),
+                };
+                self.parser.check_key_follows(last_key.as_deref(), &key, offset)?;
+                *last_key = Some(key.clone());
+                *expects_key = false;
+                return Ok(Some((offset, Event::Key(key))));
+            },
+            (Some(byte), Some(Container::Dictionary { last_key, expects_key, .. })) => {
+                let key = last_key.clone().unwrap_or_default();
+                self.parser.path.push(PathSegment::Key(key));
+                *expects_key = true;
+                byte
+            },
+        };
+
+        let event = match byte {
+            b'i' => Event::Integer(self.parser.decode_integer(byte)?),
+            b'0'..=b'9' => Event::Bytes(self.parser.decode_binarystring(byte)?),
+            b'l' => {
+                self.parser.check_depth()?;
+                self.stack.push(Container::List { count: 0 });
+                return Ok(Some((offset, Event::ListStart)));
+            },
+            b'd' => {
+                self.parser.check_depth()?;
+                self.stack.push(Container::Dictionary { count: 0, last_key: None, expects_key: true });
+                return Ok(Some((offset, Event::DictStart)));
+            },
+            _ => return Err(self.parser.error(DecodeErrorKind::InvalidStartByte)),
+        };
+        self.end_item();
+
+        Ok(Some((offset, event)))
+    }
+
+    // Items inside a container have a path segment, which is dropped once
+    // the item is complete.
+    fn end_item(&mut self) {
+        if !self.stack.is_empty() {
+            self.parser.path.pop();
+        }
+    }
+}
+
+impl<R> Iterator for Tokenizer<R>
+where
+    R: Read
+{
+    type Item = Result<(usize, Event), DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        match self.step() {
+            Ok(Some(token)) => Some(Ok(token)),
+            Ok(None) => {
+                self.done = true;
+                None
+            },
+            Err(err) => {
+                self.done = true;
+                Some(Err(err))
+            },
+        }
+    }
+}
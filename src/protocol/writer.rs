@@ -0,0 +1,194 @@
+use std::io::Write;
+
+use crate::errors::WriterError;
+use crate::protocol::encode_into;
+use crate::types::Type;
+
+
+enum Container {
+    List,
+    Dictionary {
+        last_key: Option<Vec<u8>>,
+        expects_key: bool,
+    },
+}
+
+/// Writes a single value token by token, straight to `W`, without building
+/// a `Type` first. Every call is checked against what has been written so
+/// far, so a misplaced key or `end` is reported instead of producing broken
+/// output. Unbuffered writers are best wrapped in a `BufWriter`.
+pub struct BencodeWriter<W> {
+    writer: W,
+    stack: Vec<Container>,
+    canonical: bool,
+    // Bytes still owed to a byte string started with `begin_bytes`.
+    pending: usize,
+    done: bool,
+}
+
+impl<W> BencodeWriter<W>
+where
+    W: Write
+{
+    pub fn new(writer: W) -> BencodeWriter<W> {
+        BencodeWriter { writer, stack: vec![], canonical: false, pending: 0, done: false }
+    }
+
+    /// Like `new`, but also requires dictionary keys to be written in sorted
+    /// order without duplicates, so the output is canonical bencode.
+    pub fn canonical(writer: W) -> BencodeWriter<W> {
+        BencodeWriter { canonical: true, ..BencodeWriter::new(writer) }
+    }
+
+    pub fn begin_list(&mut self) -> Result<(), WriterError> {
+        self.start_value()?;
+        self.writer.write_all(b"l")?;
+        self.stack.push(Container::List);
+
+        Ok(())
+    }
+
+    pub fn begin_dict(&mut self) -> Result<(), WriterError> {
+        self.start_value()?;
+        self.writer.write_all(b"d")?;
+        self.stack.push(Container::Dictionary { last_key: None, expects_key: true });
+
+        Ok(())
+    }
+
+    pub fn key<K>(&mut self, key: K) -> Result<(), WriterError>
+    where
+        K: AsRef<[u8]>
+    {
+        let key = key.as_ref();
+        self.check_pending()?;
+
+        let (last_key, expects_key) = match self.stack.last_mut() {
+            Some(Container::Dictionary { last_key, expects_key: expects_key @ true }) => (last_key, expects_key),
+            _ => return Err(WriterError::UnexpectedKey),
+        };
+        if self.canonical {
+            match last_key.as_deref() {
+                Some(last) if last == key => return Err(WriterError::DuplicateDictionaryKey),
+                Some(last) if last > key => return Err(WriterError::UnsortedDictionaryKeys),
+                _ => *last_key = Some(key.to_vec()),
+            }
+        }
+
+        *expects_key = false;
+        write!(self.writer, "{}:", key.len())?;
+        self.writer.write_all(key)?;
+
+        Ok(())
+    }
+
+    pub fn int(&mut self, i: i64) -> Result<(), WriterError> {
+        self.start_value()?;
+        write!(self.writer, "i{}e", i)?;
+        self.end_value();
+
+        Ok(())
+    }
+
+    pub fn bytes<B>(&mut self, bytes: B) -> Result<(), WriterError>
+    where
+        B: AsRef<[u8]>
+    {
+        let bytes = bytes.as_ref();
+        self.begin_bytes(bytes.len())?;
+
+        self.write_bytes(bytes)
+    }
+
+    /// Starts a byte string of `len` bytes whose contents are then passed to
+    /// `write_bytes` in as many pieces as needed, e.g. piece hashes as they
+    /// are computed.
+    pub fn begin_bytes(&mut self, len: usize) -> Result<(), WriterError> {
+        self.start_value()?;
+        write!(self.writer, "{}:", len)?;
+
+        match len {
+            0 => self.end_value(),
+            _ => self.pending = len,
+        }
+
+        Ok(())
+    }
+
+    pub fn write_bytes(&mut self, chunk: &[u8]) -> Result<(), WriterError> {
+        if chunk.len() > self.pending {
+            return Err(WriterError::ByteStringTooLong);
+        }
+
+        self.writer.write_all(chunk)?;
+        self.pending -= chunk.len();
+        if self.pending == 0 && !chunk.is_empty() {
+            self.end_value();
+        }
+
+        Ok(())
+    }
+
+    /// Writes a whole value at the current position.
+    pub fn value(&mut self, t: &Type) -> Result<(), WriterError> {
+        self.start_value()?;
+        encode_into(t, &mut self.writer)?;
+        self.end_value();
+
+        Ok(())
+    }
+
+    /// Closes the innermost open list or dictionary.
+    pub fn end(&mut self) -> Result<(), WriterError> {
+        self.check_pending()?;
+
+        match self.stack.last() {
+            None => return Err(WriterError::UnexpectedEnd),
+            Some(Container::Dictionary { expects_key: false, .. }) => return Err(WriterError::MissingValue),
+            Some(_) => {},
+        }
+
+        self.writer.write_all(b"e")?;
+        self.stack.pop();
+        self.end_value();
+
+        Ok(())
+    }
+
+    /// Checks that a complete value was written and returns the writer.
+    pub fn finish(self) -> Result<W, WriterError> {
+        self.check_pending()?;
+
+        match self.done {
+            true => Ok(self.writer),
+            false => Err(WriterError::Incomplete),
+        }
+    }
+
+    fn check_pending(&self) -> Result<(), WriterError> {
+        match self.pending {
+            0 => Ok(()),
+            _ => Err(WriterError::IncompleteByteString),
+        }
+    }
+
+    fn start_value(&mut self) -> Result<(), WriterError> {
+        self.check_pending()?;
+
+        match self.stack.last_mut() {
+            None if self.done => Err(WriterError::ValueAfterEnd),
+            Some(Container::Dictionary { expects_key: true, .. }) => Err(WriterError::MissingKey),
+            Some(Container::Dictionary { expects_key, .. }) => {
+                *expects_key = true;
+                Ok(())
+            },
+            _ => Ok(()),
+        }
+    }
+
+    fn end_value(&mut self) {
+        if self.stack.is_empty() {
+            self.done = true;
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use crate::errors::{DecodeError, DecodeErrorKind};
+use crate::options::DecodeOptions;
+use crate::protocol::decode_prefix;
+use crate::types::Type;
+
+/// Outcome of feeding bytes to a `PushParser`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Progress {
+    /// All bytes were taken and the value is not complete yet.
+    NeedMore,
+    /// The value is complete. The second field is how many bytes of the last
+    /// `feed` call belonged to it; the rest should be fed again for the next
+    /// value.
+    Complete(Type, usize),
+}
+
+enum State {
+    Value,
+    Integer,
+    Length(Option<usize>),
+    Bytes(usize),
+}
+
+/// Incremental decoder for input that arrives in pieces, such as a TCP
+/// stream. It buffers the bytes of the current value while following its
+/// structure, so each value is decoded only once, when its last byte
+/// arrives. Values are checked with the same `DecodeOptions` as
+/// `Decoder::decode_with`; after an error the parser starts over.
+pub struct PushParser {
+    options: DecodeOptions,
+    buf: Vec<u8>,
+    state: State,
+    // One entry per open container: `Some(expects_key)` for dictionaries and
+    // `None` for lists.
+    stack: Vec<Option<bool>>,
+    // Bytes taken by previous values, so errors point into the whole stream.
+    base: usize,
+}
+
+impl Default for PushParser {
+    fn default() -> Self {
+        PushParser::new()
+    }
+}
+
+impl PushParser {
+    pub fn new() -> PushParser {
+        PushParser::with_options(&DecodeOptions::default())
+    }
+
+    pub fn with_options(options: &DecodeOptions) -> PushParser {
+        PushParser { options: options.clone(), buf: vec![], state: State::Value, stack: vec![], base: 0 }
+    }
+
+    pub fn feed(&mut self, bytes: &[u8]) -> Result<Progress, DecodeError> {
+        let mut i = 0;
+
+        while i < bytes.len() {
+            let byte = bytes[i];
+            i += 1;
+
+            let done = match self.state {
+                State::Bytes(remaining) => {
+                    let n = remaining.min(bytes.len() - i + 1);
+                    i += n - 1;
+                    match n == remaining {
+                        true => self.end_value(),
+                        false => {
+                            self.state = State::Bytes(remaining - n);
+                            false
+                        },
+                    }
+                },
+                State::Integer => match byte {
+                    b'0'..=b'9' | b'-' => false,
+                    b'e' => self.end_value(),
+                    _ => return Err(self.fail(&bytes[..i])),
+                },
+                State::Length(len) => match (byte, len) {
+                    (b'0'..=b'9', _) => {
+                        let len = len.and_then(|l| l.checked_mul(10)).and_then(|l| l.checked_add((byte - b'0') as usize));
+                        self.state = State::Length(len);
+                        false
+                    },
+                    (b':', Some(0)) => self.end_value(),
+                    (b':', Some(len)) if len <= self.options.limits.max_string_length => {
+                        self.state = State::Bytes(len);
+                        false
+                    },
+                    _ => return Err(self.fail(&bytes[..i])),
+                },
+                State::Value => match (byte, self.stack.last().copied()) {
+                    (b'e', Some(None)) | (b'e', Some(Some(true))) => {
+                        self.stack.pop();
+                        self.end_value()
+                    },
+                    (b'0'..=b'9', _) => {
+                        self.state = State::Length(Some((byte - b'0') as usize));
+                        false
+                    },
+                    (_, Some(Some(true))) => return Err(self.fail(&bytes[..i])),
+                    (b'i', _) => {
+                        self.state = State::Integer;
+                        false
+                    },
+                    (b'l', _) | (b'd', _) if self.stack.len() < self.options.limits.max_depth => {
+                        self.stack.push(if byte == b'd' { Some(true) } else { None });
+                        false
+                    },
+                    _ => return Err(self.fail(&bytes[..i])),
+                },
+            };
+
+            if done {
+                return self.complete(&bytes[..i]);
+            }
+        }
+
+        self.buf.extend_from_slice(bytes);
+        if self.buf.len() > self.options.limits.max_input_size {
+            return Err(self.fail(&[]));
+        }
+
+        Ok(Progress::NeedMore)
+    }
+
+    /// Tells the parser that the input has ended. Fails if a value was cut
+    /// short, and succeeds if the input ended between two values.
+    pub fn finish(&mut self) -> Result<(), DecodeError> {
+        match self.is_empty() {
+            true => Ok(()),
+            false => Err(self.fail(&[])),
+        }
+    }
+
+    /// A lower bound on the number of bytes the current value still needs.
+    /// Reading at most this many bytes never reads past the end of the value.
+    pub fn min_remaining(&self) -> usize {
+        let token = match self.state {
+            State::Value => match self.stack.last() {
+                None | Some(Some(false)) => 2,
+                Some(_) => 0,
+            },
+            State::Integer | State::Length(_) => 1,
+            State::Bytes(remaining) => remaining,
+        };
+
+        token + self.stack.len()
+    }
+
+    /// Returns whether nothing of a value has been fed yet.
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty() && matches!(self.state, State::Value) && self.stack.is_empty()
+    }
+
+    // Called whenever a value ends; returns whether it was the top-level one.
+    fn end_value(&mut self) -> bool {
+        self.state = State::Value;
+
+        match self.stack.last_mut() {
+            None => true,
+            Some(Some(expects_key)) => {
+                *expects_key = !*expects_key;
+                false
+            },
+            Some(None) => false,
+        }
+    }
+
+    fn complete(&mut self, bytes: &[u8]) -> Result<Progress, DecodeError> {
+        self.buf.extend_from_slice(bytes);
+
+        let res = decode_prefix(&self.buf, &self.options);
+        let base = self.base;
+        let len = self.buf.len();
+        self.reset();
+
+        match res {
+            Ok((t, _)) => {
+                self.base = base + len;
+                Ok(Progress::Complete(t, bytes.len()))
+            },
+            Err(mut err) => {
+                err.offset += base;
+                Err(err)
+            },
+        }
+    }
+
+    // The structure tracking above gives up on exactly the input the decoder
+    // rejects, so decoding what was buffered so far yields the error with
+    // its offset and path.
+    fn fail(&mut self, bytes: &[u8]) -> DecodeError {
+        self.buf.extend_from_slice(bytes);
+
+        let mut err = match decode_prefix(&self.buf, &self.options) {
+            Err(err) => err,
+            Ok(_) => DecodeError::new(DecodeErrorKind::InvalidByteSequence, self.buf.len() - 1, String::new()),
+        };
+        err.offset += self.base;
+        self.reset();
+
+        err
+    }
+
+    fn reset(&mut self) {
+        self.buf.clear();
+        self.state = State::Value;
+        self.stack.clear();
+        self.base = 0;
+    }
+}
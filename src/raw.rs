@@ -0,0 +1,143 @@
+use std::convert::TryFrom;
+use std::fmt;
+
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::ser::{self, Serialize, Serializer};
+
+use crate::errors::DecodeError;
+use crate::options::DecodeOptions;
+use crate::protocol::{decode_slice, encode, from_bytes};
+use crate::types::Type;
+
+
+// Newtype name the serializers and deserializers of this crate recognise,
+// so that they hand over the encoded bytes instead of a value.
+pub(crate) const TOKEN: &str = "$bencode_encoder::RawValue";
+
+/// A value kept as the exact bytes it was encoded with.
+///
+/// As a field of a type read with `from_bytes` or `from_reader`, it captures
+/// the bytes of that value as they appear in the input, without building
+/// it. Writing it with `to_bytes` or `to_writer` copies the bytes back
+/// unchanged. This is how a torrent's `info` dictionary is kept so that its
+/// infohash is computed over the original bytes even when they are not in
+/// canonical form. Formats other than bencode see the decoded value.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RawValue {
+    bytes: Vec<u8>,
+}
+
+impl RawValue {
+    /// Wraps `bytes` after checking that they hold exactly one value.
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<RawValue, DecodeError> {
+        decode_slice(&bytes, &DecodeOptions::default())?;
+
+        Ok(RawValue { bytes })
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    pub fn to_type(&self) -> Result<Type, DecodeError> {
+        decode_slice(&self.bytes, &DecodeOptions::default())
+    }
+
+    /// Deserializes the value held, like `from_bytes` does.
+    pub fn decode<'a, T>(&'a self) -> Result<T, DecodeError>
+    where
+        T: Deserialize<'a>
+    {
+        from_bytes(&self.bytes)
+    }
+}
+
+impl AsRef<[u8]> for RawValue {
+    fn as_ref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl From<&Type> for RawValue {
+    fn from(t: &Type) -> Self {
+        RawValue { bytes: encode(t) }
+    }
+}
+
+impl TryFrom<&RawValue> for Type {
+    type Error = DecodeError;
+
+    fn try_from(raw: &RawValue) -> Result<Self, Self::Error> {
+        raw.to_type()
+    }
+}
+
+// The bytes themselves for this crate's serializers, which expect them
+// under `TOKEN`, and the decoded value for human readable formats.
+struct RawBytes<'a>(&'a [u8]);
+
+impl Serialize for RawBytes<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if !serializer.is_human_readable() {
+            return serializer.serialize_bytes(self.0);
+        }
+
+        match decode_slice(self.0, &DecodeOptions::default()) {
+            Ok(t) => t.serialize(serializer),
+            Err(err) => Err(ser::Error::custom(err)),
+        }
+    }
+}
+
+impl Serialize for RawValue {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_newtype_struct(TOKEN, &RawBytes(&self.bytes))
+    }
+}
+
+struct RawValueVisitor;
+
+impl<'de> Visitor<'de> for RawValueVisitor {
+    type Value = RawValue;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a bencoded value")
+    }
+
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<RawValue, E>
+    where
+        E: de::Error,
+    {
+        Ok(RawValue { bytes: v })
+    }
+
+    // Reached from deserializers other than this crate's, which do not know
+    // `TOKEN`; the value is read as a `Type` and encoded.
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<RawValue, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let t = Type::deserialize(deserializer)?;
+
+        Ok(RawValue::from(&t))
+    }
+}
+
+impl<'de> Deserialize<'de> for RawValue {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(TOKEN, RawValueVisitor)
+    }
+}
@@ -0,0 +1,649 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use serde::ser::{self, Impossible, Serialize};
+
+use crate::errors::SerError;
+use crate::protocol::{encode_bytestring, encode_integer};
+use crate::raw;
+
+
+/// Serializes `value` to bencode.
+pub fn to_bytes<T>(value: &T) -> Result<Vec<u8>, SerError>
+where
+    T: Serialize + ?Sized
+{
+    let mut bytes = vec![];
+    to_writer(&mut bytes, value)?;
+
+    Ok(bytes)
+}
+
+/// Serializes `value` to bencode, writing it to `writer`.
+///
+/// Dictionaries are buffered in memory so their keys can be sorted before
+/// they are written; everything else is written as it is serialized.
+pub fn to_writer<W, T>(writer: W, value: &T) -> Result<(), SerError>
+where
+    W: Write,
+    T: Serialize + ?Sized
+{
+    value.serialize(&mut Serializer::new(writer))
+}
+
+/// Serde serializer producing bencode.
+///
+/// Integers of any width within the `i64` range become integers and `bool`
+/// becomes `i0e` or `i1e`. Strings, chars and bytes become byte strings, and
+/// sequences and tuples become lists. Structs and maps become dictionaries
+/// whose keys are sorted, as bencode requires, with `None` values left out.
+/// Map keys may be strings, bytes or integers, which are written in decimal.
+/// Enum variants are written by name: a unit variant as a byte string, any
+/// other as a dictionary with the name as its only key. Floats, unit and
+/// `None` anywhere else have no bencode form and are rejected.
+pub struct Serializer<W> {
+    writer: W,
+    // Set while serializing a dictionary value, where a `None` directly in
+    // the entry drops it.
+    skip_none: bool,
+    // Set while serializing a `RawValue`, whose bytes are already encoded.
+    raw: bool,
+}
+
+impl<W> Serializer<W>
+where
+    W: Write
+{
+    pub fn new(writer: W) -> Serializer<W> {
+        Serializer { writer, skip_none: false, raw: false }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn integer<I>(&mut self, i: I) -> Result<(), SerError>
+    where
+        I: TryInto<i64>
+    {
+        match i.try_into() {
+            Ok(i) => Ok(encode_integer(i, &mut self.writer)?),
+            Err(_) => Err(SerError::IntegerOutOfRange),
+        }
+    }
+
+    // Variants other than unit ones are wrapped in a single entry dictionary
+    // whose closing `e` is written by the compound serializer.
+    fn begin_variant(&mut self, variant: &str) -> Result<(), SerError> {
+        self.writer.write_all(b"d")?;
+        encode_bytestring(variant.as_bytes(), &mut self.writer)?;
+
+        Ok(())
+    }
+}
+
+// Serializes a dictionary value on its own, so entries can be sorted by key
+// before they are written. An empty result means the value was `None`.
+fn value_bytes<T>(value: &T) -> Result<Vec<u8>, SerError>
+where
+    T: Serialize + ?Sized
+{
+    let mut serializer = Serializer { writer: vec![], skip_none: true, raw: false };
+    value.serialize(&mut serializer)?;
+
+    Ok(serializer.writer)
+}
+
+impl<'a, W> ser::Serializer for &'a mut Serializer<W>
+where
+    W: Write
+{
+    type Ok = ();
+    type Error = SerError;
+    type SerializeSeq = List<'a, W>;
+    type SerializeTuple = List<'a, W>;
+    type SerializeTupleStruct = List<'a, W>;
+    type SerializeTupleVariant = List<'a, W>;
+    type SerializeMap = Dictionary<'a, W>;
+    type SerializeStruct = Dictionary<'a, W>;
+    type SerializeStructVariant = Dictionary<'a, W>;
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    fn serialize_bool(self, v: bool) -> Result<(), SerError> {
+        self.integer(v as i64)
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<(), SerError> {
+        self.integer(v)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<(), SerError> {
+        self.integer(v)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<(), SerError> {
+        self.integer(v)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<(), SerError> {
+        self.integer(v)
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<(), SerError> {
+        self.integer(v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<(), SerError> {
+        self.integer(v)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<(), SerError> {
+        self.integer(v)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<(), SerError> {
+        self.integer(v)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<(), SerError> {
+        self.integer(v)
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<(), SerError> {
+        self.integer(v)
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<(), SerError> {
+        Err(SerError::UnsupportedType("f32"))
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<(), SerError> {
+        Err(SerError::UnsupportedType("f64"))
+    }
+
+    fn serialize_char(self, v: char) -> Result<(), SerError> {
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), SerError> {
+        self.serialize_bytes(v.as_bytes())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), SerError> {
+        match self.raw {
+            true => {
+                self.raw = false;
+                Ok(self.writer.write_all(v)?)
+            }
+            false => Ok(encode_bytestring(v, &mut self.writer)?),
+        }
+    }
+
+    fn serialize_none(self) -> Result<(), SerError> {
+        match self.skip_none {
+            true => Ok(()),
+            false => Err(SerError::UnsupportedNone),
+        }
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<(), SerError>
+    where
+        T: Serialize + ?Sized
+    {
+        self.skip_none = false;
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), SerError> {
+        Err(SerError::UnsupportedType("unit"))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), SerError> {
+        Err(SerError::UnsupportedType("unit struct"))
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str) -> Result<(), SerError> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<(), SerError>
+    where
+        T: Serialize + ?Sized
+    {
+        self.skip_none = false;
+        self.raw = name == raw::TOKEN;
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(self, _name: &'static str, _index: u32, variant: &'static str, value: &T) -> Result<(), SerError>
+    where
+        T: Serialize + ?Sized
+    {
+        self.skip_none = false;
+        self.begin_variant(variant)?;
+        value.serialize(&mut *self)?;
+        self.writer.write_all(b"e")?;
+
+        Ok(())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<List<'a, W>, SerError> {
+        self.skip_none = false;
+        self.writer.write_all(b"l")?;
+
+        Ok(List { ser: self, variant: false })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<List<'a, W>, SerError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<List<'a, W>, SerError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(self, _name: &'static str, _index: u32, variant: &'static str, _len: usize) -> Result<List<'a, W>, SerError> {
+        self.skip_none = false;
+        self.begin_variant(variant)?;
+        self.writer.write_all(b"l")?;
+
+        Ok(List { ser: self, variant: true })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Dictionary<'a, W>, SerError> {
+        self.skip_none = false;
+
+        Ok(Dictionary { ser: self, entries: BTreeMap::new(), key: None, variant: false })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Dictionary<'a, W>, SerError> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(self, _name: &'static str, _index: u32, variant: &'static str, _len: usize) -> Result<Dictionary<'a, W>, SerError> {
+        self.skip_none = false;
+        self.begin_variant(variant)?;
+
+        Ok(Dictionary { ser: self, entries: BTreeMap::new(), key: None, variant: true })
+    }
+}
+
+#[doc(hidden)]
+pub struct List<'a, W> {
+    ser: &'a mut Serializer<W>,
+    variant: bool,
+}
+
+impl<W> List<'_, W>
+where
+    W: Write
+{
+    fn element<T>(&mut self, value: &T) -> Result<(), SerError>
+    where
+        T: Serialize + ?Sized
+    {
+        value.serialize(&mut *self.ser)
+    }
+
+    fn finish(self) -> Result<(), SerError> {
+        match self.variant {
+            true => self.ser.writer.write_all(b"ee")?,
+            false => self.ser.writer.write_all(b"e")?,
+        }
+
+        Ok(())
+    }
+}
+
+impl<W> ser::SerializeSeq for List<'_, W>
+where
+    W: Write
+{
+    type Ok = ();
+    type Error = SerError;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), SerError>
+    where
+        T: Serialize + ?Sized
+    {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), SerError> {
+        self.finish()
+    }
+}
+
+impl<W> ser::SerializeTuple for List<'_, W>
+where
+    W: Write
+{
+    type Ok = ();
+    type Error = SerError;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), SerError>
+    where
+        T: Serialize + ?Sized
+    {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), SerError> {
+        self.finish()
+    }
+}
+
+impl<W> ser::SerializeTupleStruct for List<'_, W>
+where
+    W: Write
+{
+    type Ok = ();
+    type Error = SerError;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), SerError>
+    where
+        T: Serialize + ?Sized
+    {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), SerError> {
+        self.finish()
+    }
+}
+
+impl<W> ser::SerializeTupleVariant for List<'_, W>
+where
+    W: Write
+{
+    type Ok = ();
+    type Error = SerError;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), SerError>
+    where
+        T: Serialize + ?Sized
+    {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), SerError> {
+        self.finish()
+    }
+}
+
+#[doc(hidden)]
+pub struct Dictionary<'a, W> {
+    ser: &'a mut Serializer<W>,
+    // Encoded values by key, so they come out sorted.
+    entries: BTreeMap<Vec<u8>, Vec<u8>>,
+    key: Option<Vec<u8>>,
+    variant: bool,
+}
+
+impl<W> Dictionary<'_, W>
+where
+    W: Write
+{
+    fn entry<T>(&mut self, key: Vec<u8>, value: &T) -> Result<(), SerError>
+    where
+        T: Serialize + ?Sized
+    {
+        let value = value_bytes(value)?;
+        if value.is_empty() {
+            return Ok(());
+        }
+
+        match self.entries.insert(key, value) {
+            Some(_) => Err(SerError::DuplicateKey),
+            None => Ok(()),
+        }
+    }
+
+    fn finish(self) -> Result<(), SerError> {
+        let writer = &mut self.ser.writer;
+
+        writer.write_all(b"d")?;
+        for (k, v) in &self.entries {
+            encode_bytestring(k, writer)?;
+            writer.write_all(v)?;
+        }
+        match self.variant {
+            true => writer.write_all(b"ee")?,
+            false => writer.write_all(b"e")?,
+        }
+
+        Ok(())
+    }
+}
+
+impl<W> ser::SerializeMap for Dictionary<'_, W>
+where
+    W: Write
+{
+    type Ok = ();
+    type Error = SerError;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), SerError>
+    where
+        T: Serialize + ?Sized
+    {
+        self.key = Some(key.serialize(KeySerializer)?);
+
+        Ok(())
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), SerError>
+    where
+        T: Serialize + ?Sized
+    {
+        match self.key.take() {
+            Some(key) => self.entry(key, value),
+            None => Err(SerError::Custom("map value serialized before its key".to_string())),
+        }
+    }
+
+    fn end(self) -> Result<(), SerError> {
+        self.finish()
+    }
+}
+
+impl<W> ser::SerializeStruct for Dictionary<'_, W>
+where
+    W: Write
+{
+    type Ok = ();
+    type Error = SerError;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), SerError>
+    where
+        T: Serialize + ?Sized
+    {
+        self.entry(key.as_bytes().to_vec(), value)
+    }
+
+    fn end(self) -> Result<(), SerError> {
+        self.finish()
+    }
+}
+
+impl<W> ser::SerializeStructVariant for Dictionary<'_, W>
+where
+    W: Write
+{
+    type Ok = ();
+    type Error = SerError;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), SerError>
+    where
+        T: Serialize + ?Sized
+    {
+        self.entry(key.as_bytes().to_vec(), value)
+    }
+
+    fn end(self) -> Result<(), SerError> {
+        self.finish()
+    }
+}
+
+// Turns a map key into the bytes of a dictionary key.
+pub(crate) struct KeySerializer;
+
+impl KeySerializer {
+    fn integer<I>(i: I) -> Result<Vec<u8>, SerError>
+    where
+        I: TryInto<i64>
+    {
+        match i.try_into() {
+            Ok(i) => Ok(i.to_string().into_bytes()),
+            Err(_) => Err(SerError::IntegerOutOfRange),
+        }
+    }
+}
+
+impl ser::Serializer for KeySerializer {
+    type Ok = Vec<u8>;
+    type Error = SerError;
+    type SerializeSeq = Impossible<Vec<u8>, SerError>;
+    type SerializeTuple = Impossible<Vec<u8>, SerError>;
+    type SerializeTupleStruct = Impossible<Vec<u8>, SerError>;
+    type SerializeTupleVariant = Impossible<Vec<u8>, SerError>;
+    type SerializeMap = Impossible<Vec<u8>, SerError>;
+    type SerializeStruct = Impossible<Vec<u8>, SerError>;
+    type SerializeStructVariant = Impossible<Vec<u8>, SerError>;
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    fn serialize_bool(self, _v: bool) -> Result<Vec<u8>, SerError> {
+        Err(SerError::InvalidKey)
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Vec<u8>, SerError> {
+        KeySerializer::integer(v)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Vec<u8>, SerError> {
+        KeySerializer::integer(v)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Vec<u8>, SerError> {
+        KeySerializer::integer(v)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Vec<u8>, SerError> {
+        KeySerializer::integer(v)
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Vec<u8>, SerError> {
+        KeySerializer::integer(v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Vec<u8>, SerError> {
+        KeySerializer::integer(v)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Vec<u8>, SerError> {
+        KeySerializer::integer(v)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Vec<u8>, SerError> {
+        KeySerializer::integer(v)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Vec<u8>, SerError> {
+        KeySerializer::integer(v)
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Vec<u8>, SerError> {
+        KeySerializer::integer(v)
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<Vec<u8>, SerError> {
+        Err(SerError::InvalidKey)
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<Vec<u8>, SerError> {
+        Err(SerError::InvalidKey)
+    }
+
+    fn serialize_char(self, v: char) -> Result<Vec<u8>, SerError> {
+        Ok(v.to_string().into_bytes())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Vec<u8>, SerError> {
+        Ok(v.as_bytes().to_vec())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Vec<u8>, SerError> {
+        Ok(v.to_vec())
+    }
+
+    fn serialize_none(self) -> Result<Vec<u8>, SerError> {
+        Err(SerError::InvalidKey)
+    }
+
+    fn serialize_some<T>(self, _value: &T) -> Result<Vec<u8>, SerError>
+    where
+        T: Serialize + ?Sized
+    {
+        Err(SerError::InvalidKey)
+    }
+
+    fn serialize_unit(self) -> Result<Vec<u8>, SerError> {
+        Err(SerError::InvalidKey)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Vec<u8>, SerError> {
+        Err(SerError::InvalidKey)
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str) -> Result<Vec<u8>, SerError> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<Vec<u8>, SerError>
+    where
+        T: Serialize + ?Sized
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(self, _name: &'static str, _index: u32, _variant: &'static str, _value: &T) -> Result<Vec<u8>, SerError>
+    where
+        T: Serialize + ?Sized
+    {
+        Err(SerError::InvalidKey)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, SerError> {
+        Err(SerError::InvalidKey)
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, SerError> {
+        Err(SerError::InvalidKey)
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeTupleStruct, SerError> {
+        Err(SerError::InvalidKey)
+    }
+
+    fn serialize_tuple_variant(self, _name: &'static str, _index: u32, _variant: &'static str, _len: usize) -> Result<Self::SerializeTupleVariant, SerError> {
+        Err(SerError::InvalidKey)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, SerError> {
+        Err(SerError::InvalidKey)
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct, SerError> {
+        Err(SerError::InvalidKey)
+    }
+
+    fn serialize_struct_variant(self, _name: &'static str, _index: u32, _variant: &'static str, _len: usize) -> Result<Self::SerializeStructVariant, SerError> {
+        Err(SerError::InvalidKey)
+    }
+}
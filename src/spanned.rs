@@ -0,0 +1,79 @@
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use crate::errors::ConverterError;
+use crate::types::Type;
+
+/// A decoded value together with the range of input bytes it was decoded
+/// from, as do all the values inside it. Dictionary values also carry the
+/// range of the key they are stored under.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Spanned {
+    pub span: Range<usize>,
+    /// Where the key of a dictionary value is, or `None` for the top-level
+    /// value and list items. With duplicate keys, accepted in lenient mode,
+    /// this is the last occurrence, whose value is the one kept.
+    pub key_span: Option<Range<usize>>,
+    pub value: SpannedType,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SpannedType {
+    Integer(i64),
+    ByteString(Vec<u8>),
+    List(Vec<Spanned>),
+    Dictionary(BTreeMap<Vec<u8>, Spanned>),
+}
+
+impl Spanned {
+    /// Drops the spans, keeping only the value.
+    pub fn to_type(&self) -> Type {
+        match &self.value {
+            SpannedType::Integer(i) => Type::Integer(*i),
+            SpannedType::ByteString(s) => Type::ByteString(s.clone()),
+            SpannedType::List(l) => Type::List(l.iter().map(Spanned::to_type).collect()),
+            SpannedType::Dictionary(d) => Type::Dictionary(d.iter().map(|(k, v)| (k.clone(), v.to_type())).collect()),
+        }
+    }
+
+    /// The bytes of the value in `input`, which must be what it was decoded
+    /// from. Hashing the `info` value this way gives a torrent's infohash.
+    pub fn slice<'a>(&self, input: &'a [u8]) -> &'a [u8] {
+        &input[self.span.clone()]
+    }
+
+    pub fn get<K>(&self, key: K) -> Result<&Spanned, ConverterError>
+    where
+        K: AsRef<[u8]>,
+    {
+        match &self.value {
+            SpannedType::Dictionary(d) => match d.get(key.as_ref()) {
+                Some(t) => Ok(t),
+                None => Err(ConverterError::InvalidDictionary),
+            },
+            _ => Err(ConverterError::InvalidDictionary),
+        }
+    }
+
+    pub fn index(&self, index: usize) -> Result<&Spanned, ConverterError> {
+        match &self.value {
+            SpannedType::List(l) => match l.get(index) {
+                Some(t) => Ok(t),
+                None => Err(ConverterError::InvalidList),
+            },
+            _ => Err(ConverterError::InvalidList),
+        }
+    }
+}
+
+impl From<&Spanned> for Type {
+    fn from(value: &Spanned) -> Self {
+        value.to_type()
+    }
+}
+
+impl From<Spanned> for Type {
+    fn from(value: Spanned) -> Self {
+        value.to_type()
+    }
+}
@@ -0,0 +1,47 @@
+use std::io::Read;
+
+use crate::errors::BencodeResult;
+use crate::protocol::StreamDecoder;
+use crate::types::Type;
+
+/// Iterator over back-to-back bencoded values read from a reader, created
+/// by `Decoder::stream`. Yields each value with the byte offset it starts
+/// at and ends after the first error.
+pub struct DecodeStream<R> {
+    decoder: StreamDecoder<R>,
+    done: bool,
+}
+
+impl<R> DecodeStream<R>
+where
+    R: Read
+{
+    pub(crate) fn new(decoder: StreamDecoder<R>) -> DecodeStream<R> {
+        DecodeStream { decoder, done: false }
+    }
+}
+
+impl<R> Iterator for DecodeStream<R>
+where
+    R: Read
+{
+    type Item = BencodeResult<(usize, Type)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        match self.decoder.next_value() {
+            Ok(Some(item)) => Some(Ok(item)),
+            Ok(None) => {
+                self.done = true;
+                None
+            },
+            Err(err) => {
+                self.done = true;
+                Some(Err(err.into()))
+            },
+        }
+    }
+}
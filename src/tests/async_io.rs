@@ -0,0 +1,142 @@
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tokio::io::{AsyncRead, AsyncReadExt, ReadBuf};
+
+use crate::errors::DecodeErrorKind;
+use crate::options::{DecodeLimits, DecodeOptions};
+use crate::protocol::encode;
+use crate::{DecodeError, Decoder, Encoder, Type};
+
+
+// Hands out at most `chunk` bytes per read and fails once `data` is used up
+// when `error` is set.
+struct ChunkedReader {
+    data: Vec<u8>,
+    chunk: usize,
+    error: bool,
+}
+
+impl AsyncRead for ChunkedReader {
+    fn poll_read(mut self: Pin<&mut Self>, _cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        if self.data.is_empty() && self.error {
+            return Poll::Ready(Err(io::Error::new(io::ErrorKind::ConnectionReset, "connection reset")));
+        }
+
+        let n = self.chunk.min(buf.remaining()).min(self.data.len());
+        buf.put_slice(&self.data[..n]);
+        self.data.drain(..n);
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+fn torrent() -> Type {
+    let info = Type::Dictionary([
+        (b"length".to_vec(), Type::Integer(1 << 40)),
+        (b"pieces".to_vec(), Type::ByteString((0..=255u8).cycle().take(20000).collect())),
+    ].into_iter().collect());
+
+    Type::Dictionary([(b"info".to_vec(), info), (b"list".to_vec(), Type::List(vec![Type::List(vec![])]))].into_iter().collect())
+}
+
+#[tokio::test]
+async fn test_decode_async() {
+    let t = torrent();
+    let mut reader = &encode(&t)[..];
+
+    let res = Decoder::decode_async(&mut reader).await;
+    assert_eq!(res.ok(), Some(t));
+}
+
+#[tokio::test]
+async fn test_decode_async_does_not_read_past_value() {
+    let t = torrent();
+    let mut bytes = encode(&t);
+    bytes.extend_from_slice(b"i42e\x00raw");
+    let mut reader = &bytes[..];
+
+    assert_eq!(Decoder::decode_async(&mut reader).await.ok(), Some(t));
+    assert_eq!(Decoder::decode_async(&mut reader).await.ok(), Some(Type::Integer(42)));
+    assert_eq!(reader, b"\x00raw");
+}
+
+#[tokio::test]
+async fn test_decode_async_small_reads() {
+    let t = torrent();
+    let mut reader = ChunkedReader { data: encode(&t), chunk: 3, error: false };
+
+    let res = Decoder::decode_async(&mut reader).await;
+    assert_eq!(res.ok(), Some(t));
+}
+
+#[tokio::test]
+async fn test_decode_async_truncated() {
+    let mut reader = &b"d4:infoli1e"[..];
+
+    let err = Decoder::decode_async(&mut reader).await.err().unwrap();
+    assert_eq!(err.to_string(), "invalid list at byte 11 in info");
+}
+
+#[tokio::test]
+async fn test_decode_async_empty() {
+    let mut reader = &b""[..];
+
+    let err = Decoder::decode_async(&mut reader).await.err().unwrap();
+    let err = err.downcast_ref::<DecodeError>().unwrap();
+    assert_eq!(err.kind, DecodeErrorKind::Empty);
+}
+
+#[tokio::test]
+async fn test_decode_async_io_error() {
+    let mut reader = ChunkedReader { data: b"l4:spam".to_vec(), chunk: 2, error: true };
+
+    let err = Decoder::decode_async(&mut reader).await.err().unwrap();
+    let err = err.downcast_ref::<DecodeError>().unwrap();
+    assert_eq!(err.kind, DecodeErrorKind::Io { kind: io::ErrorKind::ConnectionReset, message: "connection reset".to_string() });
+    assert_eq!(err.offset, 7);
+}
+
+#[tokio::test]
+async fn test_decode_async_with_limits() {
+    let options = DecodeOptions::strict().with_limits(DecodeLimits { max_string_length: 16, ..DecodeLimits::default() });
+    let mut reader = &encode(&torrent())[..];
+
+    let err = Decoder::decode_async_with(&mut reader, &options).await.err().unwrap();
+    let err = err.downcast_ref::<DecodeError>().unwrap();
+    assert_eq!(err.kind, DecodeErrorKind::StringLengthLimitExceeded);
+}
+
+#[tokio::test]
+async fn test_encode_async() {
+    let t = torrent();
+    let mut writer = vec![];
+
+    Encoder::encode_async(&t, &mut writer).await.unwrap();
+    assert_eq!(writer, encode(&t));
+}
+
+#[tokio::test]
+async fn test_encode_decode_async_round_trip() {
+    let (mut client, mut server) = tokio::io::duplex(64);
+    let t = torrent();
+    let sent = t.clone();
+
+    let writer = async move {
+        Encoder::encode_async(&sent, &mut client).await.unwrap();
+        Encoder::encode_async(&Type::Integer(7), &mut client).await.unwrap();
+    };
+    let reader = async {
+        let first = Decoder::decode_async(&mut server).await.unwrap();
+        let second = Decoder::decode_async(&mut server).await.unwrap();
+        let mut rest = vec![];
+        server.read_to_end(&mut rest).await.unwrap();
+        (first, second, rest)
+    };
+
+    let (_, (first, second, rest)) = tokio::join!(writer, reader);
+    assert_eq!(first, t);
+    assert_eq!(second, Type::Integer(7));
+    assert_eq!(rest, b"");
+}
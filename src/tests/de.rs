@@ -0,0 +1,227 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+use serde_bytes::ByteBuf;
+
+use crate::errors::DecodeErrorKind;
+use crate::options::{DecodeLimits, DecodeOptions};
+use crate::protocol::decode_slice;
+use crate::{from_bytes, from_bytes_with, from_reader, from_reader_with, to_bytes, Type};
+
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct File {
+    length: u64,
+    path: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Info {
+    name: String,
+    #[serde(rename = "piece length")]
+    piece_length: u32,
+    #[serde(with = "serde_bytes")]
+    pieces: Vec<u8>,
+    files: Option<Vec<File>>,
+    #[serde(default)]
+    private: bool,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Torrent {
+    announce: String,
+    info: Info,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Borrowed<'a> {
+    name: &'a str,
+    #[serde(borrow, with = "serde_bytes")]
+    pieces: &'a [u8],
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+enum Message {
+    Ping,
+    Have(u32),
+    Request(u32, u32, u32),
+    Cancel { index: u32, begin: u32 },
+}
+
+fn torrent() -> Torrent {
+    Torrent {
+        announce: "http://tracker".to_string(),
+        info: Info {
+            name: "dir".to_string(),
+            piece_length: 16384,
+            pieces: vec![0, 159, 146, 150],
+            files: Some(vec![File { length: 5, path: vec!["a".to_string()] }, File { length: 7, path: vec!["b".to_string(), "c".to_string()] }]),
+            private: true,
+        },
+    }
+}
+
+#[test]
+fn test_from_bytes_struct() {
+    let bytes = b"d8:announce14:http://tracker4:infod5:filesld6:lengthi5e4:pathl1:aeed6:lengthi7e4:pathl1:b1:ceee4:name3:dir12:piece lengthi16384e6:pieces4:\x00\x9f\x92\x967:privatei1eee";
+
+    assert_eq!(from_bytes::<Torrent>(bytes).unwrap(), torrent());
+}
+
+#[test]
+fn test_from_bytes_round_trip() {
+    let bytes = to_bytes(&torrent()).unwrap();
+
+    assert_eq!(from_bytes::<Torrent>(&bytes).unwrap(), torrent());
+}
+
+#[test]
+fn test_from_bytes_missing_optional_fields() {
+    let bytes = b"d4:name3:dir12:piece lengthi1e6:pieces0:e";
+    let info = from_bytes::<Info>(bytes).unwrap();
+
+    assert_eq!(info.files, None);
+    assert_eq!(info.private, false);
+}
+
+#[test]
+fn test_from_bytes_borrowed() {
+    let bytes = b"d4:name4:spam6:pieces2:\xff\x00e";
+    let borrowed = from_bytes::<Borrowed>(bytes).unwrap();
+
+    assert_eq!(borrowed, Borrowed { name: "spam", pieces: b"\xff\x00" });
+    assert_eq!(borrowed.name.as_ptr(), bytes[9..].as_ptr());
+}
+
+#[test]
+fn test_from_bytes_ignores_unknown_fields() {
+    let bytes = b"d7:comment2:hi6:lengthi5e4:pathl1:ae5:extrald1:xli1eeeee";
+
+    assert_eq!(from_bytes::<File>(bytes).unwrap(), File { length: 5, path: vec!["a".to_string()] });
+}
+
+#[test]
+fn test_from_bytes_deny_unknown_fields() {
+    #[derive(Debug, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    #[allow(dead_code)]
+    struct Strict {
+        a: i64,
+    }
+
+    let err = from_bytes::<Strict>(b"d1:ai1e1:bi2ee").unwrap_err();
+    assert_eq!(err.offset, 7);
+    assert_eq!(err.to_string(), "unknown field `b`, expected `a` at byte 7");
+}
+
+#[test]
+fn test_from_bytes_error_paths() {
+    let bytes = b"d8:announce1:x4:infod5:filesld6:lengthi5e4:pathl1:aeed6:lengthi-7e4:pathl1:beee4:name3:dir12:piece lengthi1e6:pieces0:ee";
+    let err = from_bytes::<Torrent>(bytes).unwrap_err();
+    assert_eq!(err.path, "info.files[1].length");
+    assert_eq!(err.offset, 62);
+    assert_eq!(err.to_string(), "invalid value: integer `-7`, expected u64 at byte 62 in info.files[1].length");
+
+    let bytes = b"d8:announce1:x4:infod4:namei1eee";
+    let err = from_bytes::<Torrent>(bytes).unwrap_err();
+    assert_eq!(err.path, "info.name");
+    assert_eq!(err.offset, 27);
+
+    let bytes = b"d8:announce1:x4:infod4:name3:diree";
+    let err = from_bytes::<Torrent>(bytes).unwrap_err();
+    assert_eq!(err.to_string(), "missing field `piece length` at byte 20 in info");
+}
+
+#[test]
+fn test_from_bytes_decode_errors() {
+    let err = from_bytes::<Torrent>(b"d8:announce1:x4:infod4:name3:dir").unwrap_err();
+    assert_eq!(err.kind, DecodeErrorKind::InvalidDictionary);
+    assert_eq!(err.offset, 32);
+
+    let err = from_bytes::<Vec<i64>>(b"li1ei2eei3e").unwrap_err();
+    assert_eq!(err.kind, DecodeErrorKind::InvalidByteSequence);
+
+    let err = from_bytes::<(i64, i64)>(b"li1ei2ei3ee").unwrap_err();
+    assert_eq!(err.kind, DecodeErrorKind::InvalidList);
+
+    let err = from_bytes::<File>(b"").unwrap_err();
+    assert_eq!(err.kind, DecodeErrorKind::Empty);
+}
+
+#[test]
+fn test_from_bytes_options() {
+    let bytes = b"d4:pathle6:lengthi5ee";
+    let strict = DecodeOptions::strict();
+    let err = from_bytes_with::<File>(bytes, &strict).unwrap_err();
+    assert_eq!(err.kind, DecodeErrorKind::UnsortedDictionaryKeys);
+    assert_eq!(err.offset, 9);
+
+    let lenient = DecodeOptions::lenient();
+    assert_eq!(from_bytes_with::<File>(b"d6:lengthi5e4:pathleei1e", &lenient).is_ok(), true);
+
+    let limited = DecodeOptions::default().with_limits(DecodeLimits { max_depth: 2, ..DecodeLimits::default() });
+    let err = from_bytes_with::<Vec<Vec<Vec<i64>>>>(b"llleee", &limited).unwrap_err();
+    assert_eq!(err.kind, DecodeErrorKind::DepthLimitExceeded);
+}
+
+#[test]
+fn test_from_bytes_depth_capped() {
+    let unlimited = DecodeOptions::default().with_limits(DecodeLimits::unlimited());
+    let mut bytes = vec![b'l'; 100_000];
+    bytes.extend(vec![b'e'; 100_000]);
+
+    let err = from_bytes_with::<Type>(&bytes, &unlimited).unwrap_err();
+    assert_eq!(err.kind, DecodeErrorKind::DepthLimitExceeded);
+    let err = from_reader_with::<_, Type>(bytes.as_slice(), &unlimited).unwrap_err();
+    assert_eq!(err.kind, DecodeErrorKind::DepthLimitExceeded);
+
+    let mut bytes = vec![b'l'; 256];
+    bytes.extend(vec![b'e'; 256]);
+    assert_eq!(from_bytes_with::<Type>(&bytes, &unlimited).is_ok(), true);
+}
+
+#[test]
+fn test_from_bytes_enum() {
+    for message in [Message::Ping, Message::Have(7), Message::Request(1, 2, 3), Message::Cancel { index: 1, begin: 2 }] {
+        let bytes = to_bytes(&message).unwrap();
+        assert_eq!(from_bytes::<Message>(&bytes).unwrap(), message);
+    }
+
+    let err = from_bytes::<Message>(b"d4:Havei1e4:Pingi1ee").unwrap_err();
+    assert_eq!(err.kind, DecodeErrorKind::InvalidDictionary);
+
+    let err = from_bytes::<Message>(b"d6:Cancel2:xxe").unwrap_err();
+    assert_eq!(err.path, "Cancel");
+
+    let err = from_bytes::<Message>(b"4:Pong").unwrap_err();
+    assert_eq!(err.offset, 0);
+}
+
+#[test]
+fn test_from_bytes_maps_and_bools() {
+    let map = HashMap::from([(1u32, "a".to_string()), (20, "b".to_string())]);
+    let bytes = to_bytes(&map).unwrap();
+    assert_eq!(from_bytes::<HashMap<u32, String>>(&bytes).unwrap(), map);
+
+    let map = BTreeMap::from([(ByteBuf::from(b"\xff".to_vec()), true)]);
+    assert_eq!(from_bytes::<BTreeMap<ByteBuf, bool>>(b"d1:\xffi1ee").unwrap(), map);
+
+    let err = from_bytes::<bool>(b"i2e").unwrap_err();
+    assert_eq!(err.to_string(), "invalid value: integer `2`, expected 0 or 1 at byte 0");
+}
+
+#[test]
+fn test_from_bytes_type() {
+    let bytes = b"d1:ali1e2:\xff\xffe1:bd1:\xfei0eee";
+
+    assert_eq!(from_bytes::<Type>(bytes).unwrap(), decode_slice(bytes, &DecodeOptions::default()).unwrap());
+}
+
+#[test]
+fn test_from_reader() {
+    let bytes = to_bytes(&torrent()).unwrap();
+    assert_eq!(from_reader::<_, Torrent>(&bytes[..]).unwrap(), torrent());
+
+    let err = from_reader::<_, Torrent>(&bytes[..bytes.len() - 1]).unwrap_err();
+    assert_eq!(err.kind, DecodeErrorKind::InvalidDictionary);
+}
@@ -4,102 +4,119 @@ use crate::Type;
 
 #[test]
 fn test_decode_empty_string() {
-    let t = Type::ByteString("".to_string());
+    let t = Type::ByteString(b"".to_vec());
     let bytes = encode(&t);
 
     let res = decode(&mut bytes.into_iter());
     assert_eq!(res.is_ok(), true);
 
-    match res.ok().unwrap() {
-        Type::ByteString(s) => assert_eq!(s, ""),
+    match &res.ok().unwrap() {
+        Type::ByteString(s) => assert_eq!(s, b""),
         _ => panic!("error"),
     }
 }
 
 #[test]
 fn test_decode_string() {
-    let t = Type::ByteString("str".to_string());
+    let t = Type::ByteString(b"str".to_vec());
     let bytes = encode(&t);
 
     let res = decode(&mut bytes.into_iter());
     assert_eq!(res.is_ok(), true);
 
-    match res.ok().unwrap() {
-        Type::ByteString(s) => assert_eq!(s, "str"),
+    match &res.ok().unwrap() {
+        Type::ByteString(s) => assert_eq!(s, b"str"),
         _ => panic!("error"),
     }
 }
 
 #[test]
 fn test_decode_string_with_whitespaces() {
-    let t = Type::ByteString("str   str   str".to_string());
+    let t = Type::ByteString(b"str   str   str".to_vec());
     let bytes = encode(&t);
 
     let res = decode(&mut bytes.into_iter());
     assert_eq!(res.is_ok(), true);
 
-    match res.ok().unwrap() {
-        Type::ByteString(s) => assert_eq!(s, "str   str   str"),
+    match &res.ok().unwrap() {
+        Type::ByteString(s) => assert_eq!(s, b"str   str   str"),
         _ => panic!("error"),
     }
 }
 
 #[test]
 fn test_decode_string_with_numbers() {
-    let t = Type::ByteString("1222 str 11112".to_string());
+    let t = Type::ByteString(b"1222 str 11112".to_vec());
     let bytes = encode(&t);
 
     let res = decode(&mut bytes.into_iter());
     assert_eq!(res.is_ok(), true);
 
-    match res.ok().unwrap() {
-        Type::ByteString(s) => assert_eq!(s, "1222 str 11112"),
+    match &res.ok().unwrap() {
+        Type::ByteString(s) => assert_eq!(s, b"1222 str 11112"),
         _ => panic!("error"),
     }
 }
 
 #[test]
 fn test_decode_string_with_newlines() {
-    let t = Type::ByteString("1222 str 11112 \r\n 123".to_string());
+    let t = Type::ByteString(b"1222 str 11112 \r\n 123".to_vec());
     let bytes = encode(&t);
 
     let res = decode(&mut bytes.into_iter());
     assert_eq!(res.is_ok(), true);
 
-    match res.ok().unwrap() {
-        Type::ByteString(s) => assert_eq!(s, "1222 str 11112 \r\n 123"),
+    match &res.ok().unwrap() {
+        Type::ByteString(s) => assert_eq!(s, b"1222 str 11112 \r\n 123"),
         _ => panic!("error"),
     }
 }
 
 #[test]
 fn test_decode_string_with_delimiter() {
-    let t = Type::ByteString(":1".to_string());
+    let t = Type::ByteString(b":1".to_vec());
     let bytes = encode(&t);
 
     let res = decode(&mut bytes.into_iter());
     assert_eq!(res.is_ok(), true);
 
-    match res.ok().unwrap() {
-        Type::ByteString(s) => assert_eq!(s, ":1"),
+    match &res.ok().unwrap() {
+        Type::ByteString(s) => assert_eq!(s, b":1"),
         _ => panic!("error"),
     }
 }
 
 #[test]
 fn test_decode_string_binary_safe() {
-    let t = Type::ByteString("1222 str 11112 \r\n 123 ::: :::".to_string());
+    let t = Type::ByteString(b"1222 str 11112 \r\n 123 ::: :::".to_vec());
     let bytes = encode(&t);
 
     let res = decode(&mut bytes.into_iter());
     assert_eq!(res.is_ok(), true);
 
-    match res.ok().unwrap() {
-        Type::ByteString(s) => assert_eq!(s, "1222 str 11112 \r\n 123 ::: :::"),
+    match &res.ok().unwrap() {
+        Type::ByteString(s) => assert_eq!(s, b"1222 str 11112 \r\n 123 ::: :::"),
         _ => panic!("error"),
     }
 }
 
+#[test]
+fn test_decode_string_non_utf8() {
+    let bytes = (0..=255u8).collect::<Vec<u8>>();
+    let t = Type::ByteString(bytes.clone());
+    let encoded = encode(&t);
+
+    let res = decode(&mut encoded.clone().into_iter());
+    assert_eq!(res.is_ok(), true);
+
+    let decoded = res.ok().unwrap();
+    match &decoded {
+        Type::ByteString(s) => assert_eq!(*s, bytes),
+        _ => panic!("error"),
+    }
+    assert_eq!(encode(&decoded), encoded);
+}
+
 #[test]
 fn test_decode_empty_sequence() {
     let bytes = "".bytes().collect::<Vec<u8>>();
@@ -108,7 +125,7 @@ fn test_decode_empty_sequence() {
     assert_eq!(res.is_err(), true);
 
     let err = res.err().unwrap();
-    assert_eq!(err.to_string(), "empty byte sequence");
+    assert_eq!(err.to_string(), "empty byte sequence at byte 0");
 }
 
 #[test]
@@ -119,7 +136,7 @@ fn test_decode_start_with_non_digit_bytes() {
     assert_eq!(res.is_err(), true);
 
     let err = res.err().unwrap();
-    assert_eq!(err.to_string(), "invalid end byte for type: byte string");
+    assert_eq!(err.to_string(), "invalid end byte for type: byte string at byte 1");
 }
 
 #[test]
@@ -130,7 +147,7 @@ fn test_decode_missing_length() {
     assert_eq!(res.is_err(), true);
 
     let err = res.err().unwrap();
-    assert_eq!(err.to_string(), "invalid start byte");
+    assert_eq!(err.to_string(), "invalid start byte at byte 0");
 }
 
 #[test]
@@ -141,7 +158,7 @@ fn test_decode_invalid_length_less() {
     assert_eq!(res.is_err(), true);
 
     let err = res.err().unwrap();
-    assert_eq!(err.to_string(), "invalid byte string length");
+    assert_eq!(err.to_string(), "invalid byte string length at byte 3");
 }
 
 #[test]
@@ -152,7 +169,7 @@ fn test_decode_invalid_length_more() {
     assert_eq!(res.is_err(), true);
 
     let err = res.err().unwrap();
-    assert_eq!(err.to_string(), "invalid byte sequence");
+    assert_eq!(err.to_string(), "invalid byte sequence at byte 4");
 }
 
 #[test]
@@ -163,7 +180,7 @@ fn test_decode_missing_delimiter() {
     assert_eq!(res.is_err(), true);
 
     let err = res.err().unwrap();
-    assert_eq!(err.to_string(), "invalid end byte for type: byte string");
+    assert_eq!(err.to_string(), "invalid end byte for type: byte string at byte 1");
 }
 
 #[test]
@@ -174,7 +191,7 @@ fn test_decode_invalid_length_spec() {
     assert_eq!(res.is_err(), true);
 
     let err = res.err().unwrap();
-    assert_eq!(err.to_string(), "invalid end byte for type: byte string");
+    assert_eq!(err.to_string(), "invalid end byte for type: byte string at byte 1");
 }
 
 #[test]
@@ -185,5 +202,5 @@ fn test_decode_invalid_length_spec_missing_digits() {
     assert_eq!(res.is_err(), true);
 
     let err = res.err().unwrap();
-    assert_eq!(err.to_string(), "invalid start byte");
+    assert_eq!(err.to_string(), "invalid start byte at byte 0");
 }
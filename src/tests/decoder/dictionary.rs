@@ -1,5 +1,6 @@
 use std::collections::BTreeMap;
 
+use crate::errors::DecodeErrorKind;
 use crate::protocol::{decode, encode};
 use crate::Type;
 
@@ -12,7 +13,7 @@ fn test_decode_dictionary_list() {
     let res = decode(&mut bytes.into_iter());
     assert_eq!(res.is_ok(), true);
 
-    match res.ok().unwrap() {
+    match &res.ok().unwrap() {
         Type::Dictionary(d) => assert_eq!(d.len(), 0),
         _ => panic!("error"),
     }
@@ -21,19 +22,19 @@ fn test_decode_dictionary_list() {
 #[test]
 fn test_decode_dictionary_integers() {
     let t = Type::Dictionary(BTreeMap::from([
-        ("c".to_string(), Type::Integer(1)),
-        ("a".to_string(), Type::Integer(2)),
-        ("aaa".to_string(), Type::Integer(4)),
+        (b"c".to_vec(), Type::Integer(1)),
+        (b"a".to_vec(), Type::Integer(2)),
+        (b"aaa".to_vec(), Type::Integer(4)),
     ]));
     let bytes = encode(&t);
 
     let res = decode(&mut bytes.into_iter());
     assert_eq!(res.is_ok(), true);
 
-    match res.ok().unwrap() {
+    match &res.ok().unwrap() {
         Type::Dictionary(d) => {
             assert_eq!(d.len(), 3);
-            assert_eq!(d.keys().clone().map(|c| c.to_string()).collect::<Vec<String>>(), ["a", "aaa", "c"]);
+            assert_eq!(d.keys().map(|c| String::from_utf8_lossy(c)).collect::<Vec<_>>(), ["a", "aaa", "c"]);
             let values: Vec<&Type> = d.values().clone().collect();
 
             match &values[0] {
@@ -56,31 +57,31 @@ fn test_decode_dictionary_integers() {
 #[test]
 fn test_decode_dictionary_strings() {
     let t = Type::Dictionary(BTreeMap::from([
-        ("bbb".to_string(), Type::ByteString("val".to_string())),
-        ("b".to_string(), Type::ByteString("val4".to_string())),
-        ("bb".to_string(), Type::ByteString("val2".to_string())),
+        (b"bbb".to_vec(), Type::ByteString(b"val".to_vec())),
+        (b"b".to_vec(), Type::ByteString(b"val4".to_vec())),
+        (b"bb".to_vec(), Type::ByteString(b"val2".to_vec())),
     ]));
     let bytes = encode(&t);
 
     let res = decode(&mut bytes.into_iter());
     assert_eq!(res.is_ok(), true);
 
-    match res.ok().unwrap() {
+    match &res.ok().unwrap() {
         Type::Dictionary(d) => {
             assert_eq!(d.len(), 3);
-            assert_eq!(d.keys().clone().map(|c| c.to_string()).collect::<Vec<String>>(), ["b", "bb", "bbb"]);
+            assert_eq!(d.keys().map(|c| String::from_utf8_lossy(c)).collect::<Vec<_>>(), ["b", "bb", "bbb"]);
             let values: Vec<&Type> = d.values().clone().collect();
 
             match &values[0] {
-                Type::ByteString(s) => assert_eq!(*s, "val4"),
+                Type::ByteString(s) => assert_eq!(*s, b"val4"),
                 _ => panic!("error"),
             }
             match &values[1] {
-                Type::ByteString(s) => assert_eq!(*s, "val2"),
+                Type::ByteString(s) => assert_eq!(*s, b"val2"),
                 _ => panic!("error"),
             }
             match &values[2] {
-                Type::ByteString(s) => assert_eq!(*s, "val"),
+                Type::ByteString(s) => assert_eq!(*s, b"val"),
                 _ => panic!("error"),
             }
         },
@@ -91,23 +92,23 @@ fn test_decode_dictionary_strings() {
 #[test]
 fn test_decode_dictionary_integers_strings() {
     let t = Type::Dictionary(BTreeMap::from([
-        ("bbb".to_string(), Type::ByteString("val".to_string())),
-        ("b".to_string(), Type::ByteString("val4".to_string())),
-        ("bb".to_string(), Type::ByteString("val2".to_string())),
-        ("c".to_string(), Type::Integer(1)),
-        ("a".to_string(), Type::Integer(2)),
-        ("aaa".to_string(), Type::Integer(4)),
+        (b"bbb".to_vec(), Type::ByteString(b"val".to_vec())),
+        (b"b".to_vec(), Type::ByteString(b"val4".to_vec())),
+        (b"bb".to_vec(), Type::ByteString(b"val2".to_vec())),
+        (b"c".to_vec(), Type::Integer(1)),
+        (b"a".to_vec(), Type::Integer(2)),
+        (b"aaa".to_vec(), Type::Integer(4)),
     ]));
     let bytes = encode(&t);
 
     let res = decode(&mut bytes.into_iter());
     assert_eq!(res.is_ok(), true);
 
-    match res.ok().unwrap() {
+    match &res.ok().unwrap() {
         Type::Dictionary(d) => {
             assert_eq!(d.len(), 6);
             assert_eq!(
-                d.keys().clone().map(|c| c.to_string()).collect::<Vec<String>>(),
+                d.keys().map(|c| String::from_utf8_lossy(c)).collect::<Vec<_>>(),
                 ["a", "aaa", "b", "bb", "bbb", "c"],
             );
 
@@ -122,15 +123,15 @@ fn test_decode_dictionary_integers_strings() {
                 _ => panic!("error"),
             }
             match &values[2] {
-                Type::ByteString(s) => assert_eq!(*s, "val4"),
+                Type::ByteString(s) => assert_eq!(*s, b"val4"),
                 _ => panic!("error"),
             }
             match &values[3] {
-                Type::ByteString(s) => assert_eq!(*s, "val2"),
+                Type::ByteString(s) => assert_eq!(*s, b"val2"),
                 _ => panic!("error"),
             }
             match &values[4] {
-                Type::ByteString(s) => assert_eq!(*s, "val"),
+                Type::ByteString(s) => assert_eq!(*s, b"val"),
                 _ => panic!("error"),
             }
             match &values[5] {
@@ -145,25 +146,25 @@ fn test_decode_dictionary_integers_strings() {
 #[test]
 fn test_decode_dictionary_integers_strings_lists() {
     let t = Type::Dictionary(BTreeMap::from([
-        ("bbb".to_string(), Type::ByteString("val".to_string())),
-        ("b".to_string(), Type::ByteString("val4".to_string())),
-        ("bb".to_string(), Type::ByteString("val2".to_string())),
-        ("c".to_string(), Type::Integer(1)),
-        ("a".to_string(), Type::Integer(2)),
-        ("aaa".to_string(), Type::Integer(4)),
-        ("l1".to_string(), Type::List(vec![])),
-        ("l2".to_string(), Type::List(vec![Type::Integer(-1), Type::Dictionary(BTreeMap::new())])),
+        (b"bbb".to_vec(), Type::ByteString(b"val".to_vec())),
+        (b"b".to_vec(), Type::ByteString(b"val4".to_vec())),
+        (b"bb".to_vec(), Type::ByteString(b"val2".to_vec())),
+        (b"c".to_vec(), Type::Integer(1)),
+        (b"a".to_vec(), Type::Integer(2)),
+        (b"aaa".to_vec(), Type::Integer(4)),
+        (b"l1".to_vec(), Type::List(vec![])),
+        (b"l2".to_vec(), Type::List(vec![Type::Integer(-1), Type::Dictionary(BTreeMap::new())])),
     ]));
     let bytes = encode(&t);
 
     let res = decode(&mut bytes.into_iter());
     assert_eq!(res.is_ok(), true);
 
-    match res.ok().unwrap() {
+    match &res.ok().unwrap() {
         Type::Dictionary(d) => {
             assert_eq!(d.len(), 8);
             assert_eq!(
-                d.keys().clone().map(|c| c.to_string()).collect::<Vec<String>>(),
+                d.keys().map(|c| String::from_utf8_lossy(c)).collect::<Vec<_>>(),
                 ["a", "aaa", "b", "bb", "bbb", "c", "l1", "l2"],
             );
 
@@ -178,15 +179,15 @@ fn test_decode_dictionary_integers_strings_lists() {
                 _ => panic!("error"),
             }
             match &values[2] {
-                Type::ByteString(s) => assert_eq!(*s, "val4"),
+                Type::ByteString(s) => assert_eq!(*s, b"val4"),
                 _ => panic!("error"),
             }
             match &values[3] {
-                Type::ByteString(s) => assert_eq!(*s, "val2"),
+                Type::ByteString(s) => assert_eq!(*s, b"val2"),
                 _ => panic!("error"),
             }
             match &values[4] {
-                Type::ByteString(s) => assert_eq!(*s, "val"),
+                Type::ByteString(s) => assert_eq!(*s, b"val"),
                 _ => panic!("error"),
             }
             match &values[5] {
@@ -216,3 +217,64 @@ fn test_decode_dictionary_integers_strings_lists() {
         _ => panic!("error"),
     }
 }
+
+#[test]
+fn test_decode_dictionary_binary_keys() {
+    let mut bytes = "d1:".bytes().collect::<Vec<u8>>();
+    bytes.push(0x80);
+    bytes.extend("i1e2:".bytes());
+    bytes.extend([0xff, 0x00]);
+    bytes.extend("i2ee".bytes());
+
+    let res = decode(&mut bytes.clone().into_iter());
+    assert_eq!(res.is_ok(), true);
+
+    let decoded = res.ok().unwrap();
+    match &decoded {
+        Type::Dictionary(d) => {
+            assert_eq!(d.keys().cloned().collect::<Vec<Vec<u8>>>(), [vec![0x80], vec![0xff, 0x00]]);
+            assert_eq!(decoded.get([0x80]).ok(), Some(&Type::Integer(1)));
+            assert_eq!(decoded.get([0xff, 0x00]).ok(), Some(&Type::Integer(2)));
+        },
+        _ => panic!("error"),
+    }
+    assert_eq!(encode(&decoded), bytes);
+}
+
+#[test]
+fn test_decode_dictionary_error_path() {
+    let bytes = "d4:infod5:filesld6:lengthi1eed6:lengthi1x2eeeee".bytes().collect::<Vec<u8>>();
+
+    let res = decode(&mut bytes.into_iter());
+    assert_eq!(res.is_err(), true);
+
+    let err = res.err().unwrap();
+    assert_eq!(err.kind, DecodeErrorKind::InvalidEndByte("integer".to_string()));
+    assert_eq!(err.offset, 40);
+    assert_eq!(err.path, "info.files[1].length");
+    assert_eq!(err.to_string(), "invalid end byte for type: integer at byte 40 in info.files[1].length");
+}
+
+#[test]
+fn test_decode_dictionary_truncated_error_path() {
+    let bytes = "d1:ald1:b3:abc".bytes().collect::<Vec<u8>>();
+
+    let res = decode(&mut bytes.into_iter());
+    assert_eq!(res.is_err(), true);
+
+    let err = res.err().unwrap();
+    assert_eq!(err.kind, DecodeErrorKind::InvalidDictionary);
+    assert_eq!(err.to_string(), "invalid dictionary at byte 14 in a[0]");
+}
+
+#[test]
+fn test_decode_dictionary_key_without_value() {
+    let bytes = "d1:ai1e1:be".bytes().collect::<Vec<u8>>();
+
+    let res = decode(&mut bytes.into_iter());
+    assert_eq!(res.is_err(), true);
+
+    let err = res.err().unwrap();
+    assert_eq!(err.kind, DecodeErrorKind::InvalidDictionary);
+    assert_eq!(err.to_string(), "invalid dictionary at byte 10");
+}
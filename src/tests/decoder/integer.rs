@@ -79,7 +79,7 @@ fn test_decode_only_minus() {
     assert_eq!(res.is_err(), true);
 
     let err = res.err().unwrap();
-    assert_eq!(err.to_string(), "invalid integer");
+    assert_eq!(err.to_string(), "invalid integer at byte 0");
 }
 
 #[test]
@@ -90,7 +90,7 @@ fn test_decode_invalid_integer_with_letters() {
     assert_eq!(res.is_err(), true);
 
     let err = res.err().unwrap();
-    assert_eq!(err.to_string(), "invalid end byte for type: integer");
+    assert_eq!(err.to_string(), "invalid end byte for type: integer at byte 4");
 }
 
 #[test]
@@ -101,7 +101,7 @@ fn test_decode_invalid_start() {
     assert_eq!(res.is_err(), true);
 
     let err = res.err().unwrap();
-    assert_eq!(err.to_string(), "invalid integer");
+    assert_eq!(err.to_string(), "invalid integer at byte 1");
 }
 
 #[test]
@@ -112,7 +112,7 @@ fn test_decode_only_start_byte() {
     assert_eq!(res.is_err(), true);
 
     let err = res.err().unwrap();
-    assert_eq!(err.to_string(), "invalid integer");
+    assert_eq!(err.to_string(), "invalid integer at byte 1");
 }
 
 #[test]
@@ -123,7 +123,7 @@ fn test_decode_negative_zero() {
     assert_eq!(res.is_err(), true);
 
     let err = res.err().unwrap();
-    assert_eq!(err.to_string(), "negative zero is not allowed");
+    assert_eq!(err.to_string(), "negative zero is not allowed at byte 0");
 }
 
 #[test]
@@ -134,7 +134,7 @@ fn test_decode_leading_zeros() {
     assert_eq!(res.is_err(), true);
 
     let err = res.err().unwrap();
-    assert_eq!(err.to_string(), "integer with leading zeros is not allowed");
+    assert_eq!(err.to_string(), "integer with leading zeros is not allowed at byte 0");
 }
 
 #[test]
@@ -145,7 +145,7 @@ fn test_decode_multiple_leading_zeros() {
     assert_eq!(res.is_err(), true);
 
     let err = res.err().unwrap();
-    assert_eq!(err.to_string(), "integer with leading zeros is not allowed");
+    assert_eq!(err.to_string(), "integer with leading zeros is not allowed at byte 0");
 }
 
 #[test]
@@ -156,7 +156,7 @@ fn test_decode_wrong_start_byte() {
     assert_eq!(res.is_err(), true);
 
     let err = res.err().unwrap();
-    assert_eq!(err.to_string(), "invalid start byte");
+    assert_eq!(err.to_string(), "invalid start byte at byte 0");
 }
 
 #[test]
@@ -167,7 +167,7 @@ fn test_decode_empty_sequence() {
     assert_eq!(res.is_err(), true);
 
     let err = res.err().unwrap();
-    assert_eq!(err.to_string(), "empty byte sequence");
+    assert_eq!(err.to_string(), "empty byte sequence at byte 0");
 }
 
 #[test]
@@ -178,5 +178,16 @@ fn test_decode_extra_bytes() {
     assert_eq!(res.is_err(), true);
 
     let err = res.err().unwrap();
-    assert_eq!(err.to_string(), "invalid byte sequence");
+    assert_eq!(err.to_string(), "invalid byte sequence at byte 5");
+}
+
+#[test]
+fn test_decode_missing_end_byte() {
+    let bytes = "i100".bytes().collect::<Vec<u8>>();
+
+    let res = decode(&mut bytes.into_iter());
+    assert_eq!(res.is_err(), true);
+
+    let err = res.err().unwrap();
+    assert_eq!(err.to_string(), "invalid integer at byte 4");
 }
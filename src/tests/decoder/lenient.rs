@@ -0,0 +1,97 @@
+use crate::options::DecodeOptions;
+use crate::protocol::{decode_with, decode_with_warnings};
+use crate::warning::{DecodeWarning, DecodeWarningKind};
+use crate::Type;
+
+
+#[test]
+fn test_decode_lenient_canonical_has_no_warnings() {
+    let bytes = "d1:ai1e1:bl3:abcee".bytes().collect::<Vec<u8>>();
+
+    let res = decode_with_warnings(&mut bytes.into_iter(), &DecodeOptions::lenient());
+    assert_eq!(res.is_ok(), true);
+
+    let (_, warnings) = res.ok().unwrap();
+    assert_eq!(warnings.len(), 0);
+}
+
+#[test]
+fn test_decode_lenient_integer_with_leading_zeros() {
+    let bytes = "li1ei007ee".bytes().collect::<Vec<u8>>();
+
+    let res = decode_with_warnings(&mut bytes.into_iter(), &DecodeOptions::lenient());
+    assert_eq!(res.is_ok(), true);
+
+    let (t, warnings) = res.ok().unwrap();
+    assert_eq!(t, Type::List(vec![Type::Integer(1), Type::Integer(7)]));
+    assert_eq!(warnings, [DecodeWarning { kind: DecodeWarningKind::IntegerWithLeadingZeros, offset: 4 }]);
+}
+
+#[test]
+fn test_decode_lenient_negative_zero() {
+    let bytes = "i-00e".bytes().collect::<Vec<u8>>();
+
+    let res = decode_with_warnings(&mut bytes.into_iter(), &DecodeOptions::lenient());
+    assert_eq!(res.is_err(), true);
+
+    let err = res.err().unwrap();
+    assert_eq!(err.to_string(), "negative zero is not allowed at byte 0");
+}
+
+#[test]
+fn test_decode_lenient_length_with_leading_zeros() {
+    let bytes = "003:abc".bytes().collect::<Vec<u8>>();
+
+    let res = decode_with_warnings(&mut bytes.into_iter(), &DecodeOptions::lenient());
+    assert_eq!(res.is_ok(), true);
+
+    let (t, warnings) = res.ok().unwrap();
+    assert_eq!(t, Type::ByteString(b"abc".to_vec()));
+    assert_eq!(warnings, [DecodeWarning { kind: DecodeWarningKind::ByteStringLengthWithLeadingZeros, offset: 0 }]);
+}
+
+#[test]
+fn test_decode_lenient_unsorted_and_duplicate_keys() {
+    let bytes = "d1:bi1e1:ai2e1:bi3ee".bytes().collect::<Vec<u8>>();
+
+    let res = decode_with_warnings(&mut bytes.into_iter(), &DecodeOptions::lenient());
+    assert_eq!(res.is_ok(), true);
+
+    let (t, warnings) = res.ok().unwrap();
+    assert_eq!(t.get("a").ok(), Some(&Type::Integer(2)));
+    assert_eq!(t.get("b").ok(), Some(&Type::Integer(3)));
+    assert_eq!(warnings, [
+        DecodeWarning { kind: DecodeWarningKind::UnsortedDictionaryKeys, offset: 7 },
+        DecodeWarning { kind: DecodeWarningKind::DuplicateDictionaryKey, offset: 13 },
+    ]);
+}
+
+#[test]
+fn test_decode_lenient_trailing_bytes() {
+    let bytes = "i42e\r\njunk".bytes().collect::<Vec<u8>>();
+
+    let res = decode_with_warnings(&mut bytes.into_iter(), &DecodeOptions::lenient());
+    assert_eq!(res.is_ok(), true);
+
+    let (t, warnings) = res.ok().unwrap();
+    assert_eq!(t, Type::Integer(42));
+    assert_eq!(warnings, [DecodeWarning { kind: DecodeWarningKind::TrailingBytes, offset: 4 }]);
+}
+
+#[test]
+fn test_decode_lenient_still_rejects_malformed() {
+    let bytes = "d1:ai1e".bytes().collect::<Vec<u8>>();
+
+    let res = decode_with(&mut bytes.into_iter(), &DecodeOptions::lenient());
+    assert_eq!(res.is_err(), true);
+
+    let err = res.err().unwrap();
+    assert_eq!(err.to_string(), "invalid dictionary at byte 7");
+}
+
+#[test]
+fn test_decode_warning_display() {
+    let warning = DecodeWarning { kind: DecodeWarningKind::TrailingBytes, offset: 12 };
+
+    assert_eq!(warning.to_string(), "trailing bytes after value at byte 12");
+}
@@ -0,0 +1,125 @@
+use crate::errors::DecodeErrorKind;
+use crate::options::{DecodeLimits, DecodeOptions};
+use crate::protocol::{decode, decode_with};
+use crate::Type;
+
+
+fn options(limits: DecodeLimits) -> DecodeOptions {
+    DecodeOptions::default().with_limits(limits)
+}
+
+#[test]
+fn test_decode_hostile_nesting_default_limits() {
+    let bytes = vec![b'l'; 1_000_000];
+
+    let res = decode(&mut bytes.into_iter());
+    assert_eq!(res.is_err(), true);
+
+    let err = res.err().unwrap();
+    assert_eq!(err.kind, DecodeErrorKind::DepthLimitExceeded);
+    assert_eq!(err.offset, 256);
+}
+
+#[test]
+fn test_decode_depth_within_limit() {
+    let bytes = "llleee".bytes().collect::<Vec<u8>>();
+    let limits = DecodeLimits { max_depth: 3, ..DecodeLimits::unlimited() };
+
+    let res = decode_with(&mut bytes.into_iter(), &options(limits));
+    assert_eq!(res.is_ok(), true);
+}
+
+#[test]
+fn test_decode_depth_limit_exceeded() {
+    let bytes = "d1:ald1:bleeee".bytes().collect::<Vec<u8>>();
+    let limits = DecodeLimits { max_depth: 3, ..DecodeLimits::unlimited() };
+
+    let res = decode_with(&mut bytes.into_iter(), &options(limits));
+    assert_eq!(res.is_err(), true);
+
+    let err = res.err().unwrap();
+    assert_eq!(err.kind, DecodeErrorKind::DepthLimitExceeded);
+    assert_eq!(err.to_string(), "nesting depth limit exceeded at byte 9 in a[0].b");
+}
+
+#[test]
+fn test_decode_string_length_limit_exceeded() {
+    let bytes = "99999999999:abc".bytes().collect::<Vec<u8>>();
+    let limits = DecodeLimits { max_string_length: 1024, ..DecodeLimits::unlimited() };
+
+    let res = decode_with(&mut bytes.into_iter(), &options(limits));
+    assert_eq!(res.is_err(), true);
+
+    let err = res.err().unwrap();
+    assert_eq!(err.kind, DecodeErrorKind::StringLengthLimitExceeded);
+    assert_eq!(err.offset, 0);
+}
+
+#[test]
+fn test_decode_string_length_at_limit() {
+    let bytes = "3:abc".bytes().collect::<Vec<u8>>();
+    let limits = DecodeLimits { max_string_length: 3, ..DecodeLimits::unlimited() };
+
+    let res = decode_with(&mut bytes.into_iter(), &options(limits));
+    assert_eq!(res.ok(), Some(Type::ByteString(b"abc".to_vec())));
+}
+
+#[test]
+fn test_decode_dictionary_key_length_limit_exceeded() {
+    let bytes = "d5:abcdei1ee".bytes().collect::<Vec<u8>>();
+    let limits = DecodeLimits { max_string_length: 4, ..DecodeLimits::unlimited() };
+
+    let res = decode_with(&mut bytes.into_iter(), &options(limits));
+    assert_eq!(res.is_err(), true);
+
+    let err = res.err().unwrap();
+    assert_eq!(err.kind, DecodeErrorKind::StringLengthLimitExceeded);
+}
+
+#[test]
+fn test_decode_list_item_limit_exceeded() {
+    let bytes = "li1ei2ei3ee".bytes().collect::<Vec<u8>>();
+    let limits = DecodeLimits { max_items: 2, ..DecodeLimits::unlimited() };
+
+    let res = decode_with(&mut bytes.into_iter(), &options(limits));
+    assert_eq!(res.is_err(), true);
+
+    let err = res.err().unwrap();
+    assert_eq!(err.kind, DecodeErrorKind::ItemLimitExceeded);
+    assert_eq!(err.offset, 7);
+}
+
+#[test]
+fn test_decode_dictionary_item_limit_exceeded() {
+    let bytes = "d1:ai1e1:bi2e1:ci3ee".bytes().collect::<Vec<u8>>();
+    let limits = DecodeLimits { max_items: 2, ..DecodeLimits::unlimited() };
+
+    let res = decode_with(&mut bytes.into_iter(), &options(limits));
+    assert_eq!(res.is_err(), true);
+
+    let err = res.err().unwrap();
+    assert_eq!(err.kind, DecodeErrorKind::ItemLimitExceeded);
+    assert_eq!(err.offset, 13);
+}
+
+#[test]
+fn test_decode_input_size_limit_exceeded() {
+    let bytes = "l3:abc3:defe".bytes().collect::<Vec<u8>>();
+    let limits = DecodeLimits { max_input_size: 8, ..DecodeLimits::unlimited() };
+
+    let res = decode_with(&mut bytes.into_iter(), &options(limits));
+    assert_eq!(res.is_err(), true);
+
+    let err = res.err().unwrap();
+    assert_eq!(err.kind, DecodeErrorKind::InputSizeLimitExceeded);
+    assert_eq!(err.offset, 8);
+}
+
+#[test]
+fn test_decode_input_size_at_limit() {
+    let bytes = "l3:abc3:defe".bytes().collect::<Vec<u8>>();
+    let limits = DecodeLimits { max_input_size: 12, ..DecodeLimits::unlimited() };
+
+    let res = decode_with(&mut bytes.into_iter(), &options(limits));
+    assert_eq!(res.is_ok(), true);
+}
@@ -1,6 +1,8 @@
 use std::collections::BTreeMap;
 
-use crate::protocol::{decode, encode};
+use crate::errors::DecodeErrorKind;
+use crate::options::{DecodeLimits, DecodeOptions};
+use crate::protocol::{decode, decode_with, encode};
 use crate::Type;
 
 
@@ -12,7 +14,7 @@ fn test_decode_empty_list() {
     let res = decode(&mut bytes.into_iter());
     assert_eq!(res.is_ok(), true);
 
-    match res.ok().unwrap() {
+    match &res.ok().unwrap() {
         Type::List(v) => assert_eq!(v.len(), 0),
         _ => panic!("error"),
     }
@@ -30,7 +32,7 @@ fn test_decode_list_of_integers() {
     let res = decode(&mut bytes.into_iter());
     assert_eq!(res.is_ok(), true);
 
-    match res.ok().unwrap() {
+    match &res.ok().unwrap() {
         Type::List(v) => {
             assert_eq!(v.len(), 3);
 
@@ -54,34 +56,34 @@ fn test_decode_list_of_integers() {
 #[test]
 fn test_decode_list_of_binary_strings() {
     let t = Type::List(vec![
-        Type::ByteString("123 abc\n".to_string()),
-        Type::ByteString("ok".to_string()),
-        Type::ByteString("rust".to_string()),
-        Type::ByteString("bencode 2023 bencode".to_string()),
+        Type::ByteString(b"123 abc\n".to_vec()),
+        Type::ByteString(b"ok".to_vec()),
+        Type::ByteString(b"rust".to_vec()),
+        Type::ByteString(b"bencode 2023 bencode".to_vec()),
     ]);
     let bytes = encode(&t);
 
     let res = decode(&mut bytes.into_iter());
     assert_eq!(res.is_ok(), true);
 
-    match res.ok().unwrap() {
+    match &res.ok().unwrap() {
         Type::List(v) => {
             assert_eq!(v.len(), 4);
 
             match &v[0] {
-                Type::ByteString(s)=> assert_eq!(*s, "123 abc\n"),
+                Type::ByteString(s)=> assert_eq!(*s, b"123 abc\n"),
                 _ => panic!("error"),
             }
             match &v[1] {
-                Type::ByteString(s)=> assert_eq!(*s, "ok"),
+                Type::ByteString(s)=> assert_eq!(*s, b"ok"),
                 _ => panic!("error"),
             }
             match &v[2] {
-                Type::ByteString(s)=> assert_eq!(*s, "rust"),
+                Type::ByteString(s)=> assert_eq!(*s, b"rust"),
                 _ => panic!("error"),
             }
             match &v[3] {
-                Type::ByteString(s)=> assert_eq!(*s, "bencode 2023 bencode"),
+                Type::ByteString(s)=> assert_eq!(*s, b"bencode 2023 bencode"),
                 _ => panic!("error"),
             }
         },
@@ -92,11 +94,11 @@ fn test_decode_list_of_binary_strings() {
 #[test]
 fn test_decode_list_of_integers_and_strings() {
     let t = Type::List(vec![
-        Type::ByteString("123 abc\n".to_string()),
+        Type::ByteString(b"123 abc\n".to_vec()),
         Type::Integer(1),
-        Type::ByteString("ok".to_string()),
-        Type::ByteString("rust".to_string()),
-        Type::ByteString("bencode 2023 bencode".to_string()),
+        Type::ByteString(b"ok".to_vec()),
+        Type::ByteString(b"rust".to_vec()),
+        Type::ByteString(b"bencode 2023 bencode".to_vec()),
         Type::Integer(-54),
     ]);
     let bytes = encode(&t);
@@ -104,12 +106,12 @@ fn test_decode_list_of_integers_and_strings() {
     let res = decode(&mut bytes.into_iter());
     assert_eq!(res.is_ok(), true);
 
-    match res.ok().unwrap() {
+    match &res.ok().unwrap() {
         Type::List(v) => {
             assert_eq!(v.len(), 6);
 
             match &v[0] {
-                Type::ByteString(s)=> assert_eq!(*s, "123 abc\n"),
+                Type::ByteString(s)=> assert_eq!(*s, b"123 abc\n"),
                 _ => panic!("error"),
             }
             match &v[1] {
@@ -117,15 +119,15 @@ fn test_decode_list_of_integers_and_strings() {
                 _ => panic!("error"),
             }
             match &v[2] {
-                Type::ByteString(s)=> assert_eq!(*s, "ok"),
+                Type::ByteString(s)=> assert_eq!(*s, b"ok"),
                 _ => panic!("error"),
             }
             match &v[3] {
-                Type::ByteString(s)=> assert_eq!(*s, "rust"),
+                Type::ByteString(s)=> assert_eq!(*s, b"rust"),
                 _ => panic!("error"),
             }
             match &v[4] {
-                Type::ByteString(s)=> assert_eq!(*s, "bencode 2023 bencode"),
+                Type::ByteString(s)=> assert_eq!(*s, b"bencode 2023 bencode"),
                 _ => panic!("error"),
             }
             match &v[5] {
@@ -140,16 +142,16 @@ fn test_decode_list_of_integers_and_strings() {
 #[test]
 fn test_decode_list_of_integers_and_strings_and_lists() {
     let t = Type::List(vec![
-        Type::ByteString("123 abc\n".to_string()),
+        Type::ByteString(b"123 abc\n".to_vec()),
         Type::Integer(1),
         Type::List(vec![]),
-        Type::ByteString("ok".to_string()),
-        Type::ByteString("rust".to_string()),
+        Type::ByteString(b"ok".to_vec()),
+        Type::ByteString(b"rust".to_vec()),
         Type::List(vec![
-            Type::ByteString("bytestr".to_string()),
+            Type::ByteString(b"bytestr".to_vec()),
             Type::Integer(202),
         ]),
-        Type::ByteString("bencode 2023 bencode".to_string()),
+        Type::ByteString(b"bencode 2023 bencode".to_vec()),
         Type::Integer(-54),
     ]);
     let bytes = encode(&t);
@@ -157,12 +159,12 @@ fn test_decode_list_of_integers_and_strings_and_lists() {
     let res = decode(&mut bytes.into_iter());
     assert_eq!(res.is_ok(), true);
 
-    match res.ok().unwrap() {
+    match &res.ok().unwrap() {
         Type::List(v) => {
             assert_eq!(v.len(), 8);
 
             match &v[0] {
-                Type::ByteString(s)=> assert_eq!(*s, "123 abc\n"),
+                Type::ByteString(s)=> assert_eq!(*s, b"123 abc\n"),
                 _ => panic!("error"),
             }
             match &v[1] {
@@ -174,11 +176,11 @@ fn test_decode_list_of_integers_and_strings_and_lists() {
                 _ => panic!("error"),
             }
             match &v[3] {
-                Type::ByteString(s)=> assert_eq!(*s, "ok"),
+                Type::ByteString(s)=> assert_eq!(*s, b"ok"),
                 _ => panic!("error"),
             }
             match &v[4] {
-                Type::ByteString(s)=> assert_eq!(*s, "rust"),
+                Type::ByteString(s)=> assert_eq!(*s, b"rust"),
                 _ => panic!("error"),
             }
             match &v[5] {
@@ -186,7 +188,7 @@ fn test_decode_list_of_integers_and_strings_and_lists() {
                     assert_eq!(l.len(), 2);
 
                     match &l[0] {
-                        Type::ByteString(s)=> assert_eq!(*s, "bytestr"),
+                        Type::ByteString(s)=> assert_eq!(*s, b"bytestr"),
                         _ => panic!("error"),
                     }
                     match &l[1] {
@@ -197,7 +199,7 @@ fn test_decode_list_of_integers_and_strings_and_lists() {
                 _ => panic!("error"),
             }
             match &v[6] {
-                Type::ByteString(s)=> assert_eq!(*s, "bencode 2023 bencode"),
+                Type::ByteString(s)=> assert_eq!(*s, b"bencode 2023 bencode"),
                 _ => panic!("error"),
             }
             match &v[7] {
@@ -213,17 +215,17 @@ fn test_decode_list_of_integers_and_strings_and_lists() {
 fn test_decode_list_of_lists() {
     let t = Type::List(vec![
         Type::List(vec![
-            Type::ByteString("123 abc\n".to_string()),
+            Type::ByteString(b"123 abc\n".to_vec()),
             Type::Integer(1),
-            Type::ByteString("bencode 2023 bencode".to_string()),
+            Type::ByteString(b"bencode 2023 bencode".to_vec()),
             Type::Integer(-54),
         ]),
         Type::List(vec![
-            Type::ByteString("ok".to_string()),
-            Type::ByteString("rust".to_string()),
+            Type::ByteString(b"ok".to_vec()),
+            Type::ByteString(b"rust".to_vec()),
         ]),
         Type::List(vec![
-            Type::ByteString("bytestr".to_string()),
+            Type::ByteString(b"bytestr".to_vec()),
             Type::Integer(202),
         ]),
     ]);
@@ -232,7 +234,7 @@ fn test_decode_list_of_lists() {
     let res = decode(&mut bytes.into_iter());
     assert_eq!(res.is_ok(), true);
 
-    match res.ok().unwrap() {
+    match &res.ok().unwrap() {
         Type::List(v) => {
             assert_eq!(v.len(), 3);
 
@@ -240,7 +242,7 @@ fn test_decode_list_of_lists() {
                 Type::List(l) => {
                     assert_eq!(l.len(), 4);
                     match &l[0] {
-                        Type::ByteString(s)=> assert_eq!(*s, "123 abc\n"),
+                        Type::ByteString(s)=> assert_eq!(*s, b"123 abc\n"),
                         _ => panic!("error"),
                     }
                     match &l[1] {
@@ -248,7 +250,7 @@ fn test_decode_list_of_lists() {
                         _ => panic!("error"),
                     }
                     match &l[2] {
-                        Type::ByteString(s) => assert_eq!(*s, "bencode 2023 bencode"),
+                        Type::ByteString(s) => assert_eq!(*s, b"bencode 2023 bencode"),
                         _ => panic!("error"),
                     }
                     match &l[3] {
@@ -262,11 +264,11 @@ fn test_decode_list_of_lists() {
                 Type::List(l) => {
                     assert_eq!(l.len(), 2);
                     match &l[0] {
-                        Type::ByteString(s)=> assert_eq!(*s, "ok"),
+                        Type::ByteString(s)=> assert_eq!(*s, b"ok"),
                         _ => panic!("error"),
                     }
                     match &l[1] {
-                        Type::ByteString(s) => assert_eq!(*s, "rust"),
+                        Type::ByteString(s) => assert_eq!(*s, b"rust"),
                         _ => panic!("error"),
                     }
                 },
@@ -277,7 +279,7 @@ fn test_decode_list_of_lists() {
                     assert_eq!(l.len(), 2);
 
                     match &l[0] {
-                        Type::ByteString(s)=> assert_eq!(*s, "bytestr"),
+                        Type::ByteString(s)=> assert_eq!(*s, b"bytestr"),
                         _ => panic!("error"),
                     }
                     match &l[1] {
@@ -296,12 +298,12 @@ fn test_decode_list_of_lists() {
 fn test_decode_list_of_dictionaries() {
     let t = Type::List(vec![
         Type::Dictionary(BTreeMap::from([
-            ("k".to_string(), Type::Integer(1)),
-            ("c".to_string(), Type::ByteString("abc".to_string())),
+            (b"k".to_vec(), Type::Integer(1)),
+            (b"c".to_vec(), Type::ByteString(b"abc".to_vec())),
         ])),
         Type::Dictionary(BTreeMap::from([
-            ("a".to_string(), Type::Integer(101)),
-            ("b".to_string(), Type::Integer(539)),
+            (b"a".to_vec(), Type::Integer(101)),
+            (b"b".to_vec(), Type::Integer(539)),
         ])),
     ]);
     let bytes = encode(&t);
@@ -309,19 +311,19 @@ fn test_decode_list_of_dictionaries() {
     let res = decode(&mut bytes.into_iter());
     assert_eq!(res.is_ok(), true);
 
-    match res.ok().unwrap() {
+    match &res.ok().unwrap() {
         Type::List(v) => {
             assert_eq!(v.len(), 2);
 
             match &v[0] {
                 Type::Dictionary(d) => {
-                    assert_eq!(d.keys().clone().map(|c| c.to_string()).collect::<Vec<String>>(), ["c", "k"]);
+                    assert_eq!(d.keys().map(|c| String::from_utf8_lossy(c)).collect::<Vec<_>>(), ["c", "k"]);
                 },
                 _ => panic!("error"),
             }
             match &v[1] {
                 Type::Dictionary(d) => {
-                    assert_eq!(d.keys().clone().map(|c| c.to_string()).collect::<Vec<String>>(), ["a", "b"]);
+                    assert_eq!(d.keys().map(|c| String::from_utf8_lossy(c)).collect::<Vec<_>>(), ["a", "b"]);
                 },
                 _ => panic!("error"),
             }
@@ -334,12 +336,12 @@ fn test_decode_list_of_dictionaries() {
 fn test_decode_list_of_dictionaries_check_values() {
     let t = Type::List(vec![
         Type::Dictionary(BTreeMap::from([
-            ("k".to_string(), Type::Integer(1)),
-            ("c".to_string(), Type::ByteString("abc".to_string())),
+            (b"k".to_vec(), Type::Integer(1)),
+            (b"c".to_vec(), Type::ByteString(b"abc".to_vec())),
         ])),
         Type::Dictionary(BTreeMap::from([
-            ("a".to_string(), Type::Integer(101)),
-            ("b".to_string(), Type::Integer(-539)),
+            (b"a".to_vec(), Type::Integer(101)),
+            (b"b".to_vec(), Type::Integer(-539)),
         ])),
     ]);
     let bytes = encode(&t);
@@ -347,18 +349,18 @@ fn test_decode_list_of_dictionaries_check_values() {
     let res = decode(&mut bytes.into_iter());
     assert_eq!(res.is_ok(), true);
 
-    match res.ok().unwrap() {
+    match &res.ok().unwrap() {
         Type::List(v) => {
             assert_eq!(v.len(), 2);
 
             match &v[0] {
                 Type::Dictionary(d) => {
-                    assert_eq!(d.keys().clone().map(|c| c.to_string()).collect::<Vec<String>>(), ["c", "k"]);
+                    assert_eq!(d.keys().map(|c| String::from_utf8_lossy(c)).collect::<Vec<_>>(), ["c", "k"]);
                     let values: Vec<&Type> = d.values().clone().collect();
                     assert_eq!(values.len(), 2);
 
                     match &values[0] {
-                        Type::ByteString(s) => assert_eq!(*s, "abc"),
+                        Type::ByteString(s) => assert_eq!(*s, b"abc"),
                         _ => panic!("error"),
                     }
                     match &values[1] {
@@ -370,7 +372,7 @@ fn test_decode_list_of_dictionaries_check_values() {
             }
             match &v[1] {
                 Type::Dictionary(d) => {
-                    assert_eq!(d.keys().clone().map(|c| c.to_string()).collect::<Vec<String>>(), ["a", "b"]);
+                    assert_eq!(d.keys().map(|c| String::from_utf8_lossy(c)).collect::<Vec<_>>(), ["a", "b"]);
                     let values: Vec<&Type> = d.values().clone().collect();
                     assert_eq!(values.len(), 2);
 
@@ -394,8 +396,8 @@ fn test_decode_list_of_dictionaries_check_values() {
 fn test_decode_list_of_dictionaries_check_values_empty_dict() {
     let t = Type::List(vec![
         Type::Dictionary(BTreeMap::from([
-            ("k".to_string(), Type::Integer(1)),
-            ("c".to_string(), Type::ByteString("abc".to_string())),
+            (b"k".to_vec(), Type::Integer(1)),
+            (b"c".to_vec(), Type::ByteString(b"abc".to_vec())),
         ])),
         Type::Dictionary(BTreeMap::new()),
     ]);
@@ -404,18 +406,18 @@ fn test_decode_list_of_dictionaries_check_values_empty_dict() {
     let res = decode(&mut bytes.into_iter());
     assert_eq!(res.is_ok(), true);
 
-    match res.ok().unwrap() {
+    match &res.ok().unwrap() {
         Type::List(v) => {
             assert_eq!(v.len(), 2);
 
             match &v[0] {
                 Type::Dictionary(d) => {
-                    assert_eq!(d.keys().clone().map(|c| c.to_string()).collect::<Vec<String>>(), ["c", "k"]);
+                    assert_eq!(d.keys().map(|c| String::from_utf8_lossy(c)).collect::<Vec<_>>(), ["c", "k"]);
                     let values: Vec<&Type> = d.values().clone().collect();
                     assert_eq!(values.len(), 2);
 
                     match &values[0] {
-                        Type::ByteString(s) => assert_eq!(*s, "abc"),
+                        Type::ByteString(s) => assert_eq!(*s, b"abc"),
                         _ => panic!("error"),
                     }
                     match &values[1] {
@@ -427,7 +429,7 @@ fn test_decode_list_of_dictionaries_check_values_empty_dict() {
             }
             match &v[1] {
                 Type::Dictionary(d) => {
-                    let keys = d.keys().clone().map(|c| c.to_string()).collect::<Vec<String>>();
+                    let keys = d.keys().map(|c| String::from_utf8_lossy(c)).collect::<Vec<_>>();
                     assert_eq!(keys.len(), 0);
                     let values: Vec<&Type> = d.values().clone().collect();
                     assert_eq!(values.len(), 0);
@@ -443,17 +445,17 @@ fn test_decode_list_of_dictionaries_check_values_empty_dict() {
 fn test_decode_list_all() {
     let t = Type::List(vec![
         Type::Dictionary(BTreeMap::from([
-            ("k".to_string(), Type::Integer(1)),
-            ("c".to_string(), Type::ByteString("abc".to_string())),
+            (b"k".to_vec(), Type::Integer(1)),
+            (b"c".to_vec(), Type::ByteString(b"abc".to_vec())),
         ])),
         Type::Dictionary(BTreeMap::from([
-            ("a".to_string(), Type::Integer(101)),
-            ("b".to_string(), Type::Integer(-539)),
+            (b"a".to_vec(), Type::Integer(101)),
+            (b"b".to_vec(), Type::Integer(-539)),
         ])),
         Type::List(vec![
-            Type::ByteString("123 abc\n".to_string()),
+            Type::ByteString(b"123 abc\n".to_vec()),
             Type::Integer(1),
-            Type::ByteString("bencode 2023 bencode".to_string()),
+            Type::ByteString(b"bencode 2023 bencode".to_vec()),
             Type::Integer(-54),
         ]),
     ]);
@@ -462,18 +464,18 @@ fn test_decode_list_all() {
     let res = decode(&mut bytes.into_iter());
     assert_eq!(res.is_ok(), true);
 
-    match res.ok().unwrap() {
+    match &res.ok().unwrap() {
         Type::List(v) => {
             assert_eq!(v.len(), 3);
 
             match &v[0] {
                 Type::Dictionary(d) => {
-                    assert_eq!(d.keys().clone().map(|c| c.to_string()).collect::<Vec<String>>(), ["c", "k"]);
+                    assert_eq!(d.keys().map(|c| String::from_utf8_lossy(c)).collect::<Vec<_>>(), ["c", "k"]);
                     let values: Vec<&Type> = d.values().clone().collect();
                     assert_eq!(values.len(), 2);
 
                     match &values[0] {
-                        Type::ByteString(s) => assert_eq!(*s, "abc"),
+                        Type::ByteString(s) => assert_eq!(*s, b"abc"),
                         _ => panic!("error"),
                     }
                     match &values[1] {
@@ -485,7 +487,7 @@ fn test_decode_list_all() {
             }
             match &v[1] {
                 Type::Dictionary(d) => {
-                    assert_eq!(d.keys().clone().map(|c| c.to_string()).collect::<Vec<String>>(), ["a", "b"]);
+                    assert_eq!(d.keys().map(|c| String::from_utf8_lossy(c)).collect::<Vec<_>>(), ["a", "b"]);
                     let values: Vec<&Type> = d.values().clone().collect();
                     assert_eq!(values.len(), 2);
 
@@ -504,7 +506,7 @@ fn test_decode_list_all() {
                 Type::List(l) => {
                     assert_eq!(l.len(), 4);
                     match &l[0] {
-                        Type::ByteString(s)=> assert_eq!(*s, "123 abc\n"),
+                        Type::ByteString(s)=> assert_eq!(*s, b"123 abc\n"),
                         _ => panic!("error"),
                     }
                     match &l[1] {
@@ -512,7 +514,7 @@ fn test_decode_list_all() {
                         _ => panic!("error"),
                     }
                     match &l[2] {
-                        Type::ByteString(s) => assert_eq!(*s, "bencode 2023 bencode"),
+                        Type::ByteString(s) => assert_eq!(*s, b"bencode 2023 bencode"),
                         _ => panic!("error"),
                     }
                     match &l[3] {
@@ -526,3 +528,80 @@ fn test_decode_list_all() {
         _ => panic!("error"),
     }
 }
+
+fn nesting_depth(mut t: &Type) -> usize {
+    let mut depth = 0;
+
+    while let Type::List(l) = t {
+        depth += 1;
+        t = match l.last() {
+            None => break,
+            Some(inner) => inner,
+        };
+    }
+
+    depth
+}
+
+// Decoding, encoding and dropping values far deeper than the default limit
+// must not use the stack once per level.
+#[test]
+fn test_decode_deeply_nested_lists_on_small_stack() {
+    let depth = 100_000;
+    let mut lists = vec![b'l'; depth];
+    lists.extend(vec![b'e'; depth]);
+    let mut dictionaries = "d1:k".repeat(depth).into_bytes();
+    dictionaries.extend(b"le");
+    dictionaries.extend(vec![b'e'; depth]);
+
+    let handle = std::thread::Builder::new()
+        .stack_size(64 * 1024)
+        .spawn(move || {
+            let options = DecodeOptions::default().with_limits(DecodeLimits::unlimited());
+            let t = decode_with(&mut lists.clone().into_iter(), &options).unwrap();
+            let d = decode_with(&mut dictionaries.clone().into_iter(), &options).unwrap();
+            (nesting_depth(&t), encode(&t) == lists, encode(&d) == dictionaries)
+        })
+        .unwrap();
+
+    assert_eq!(handle.join().unwrap(), (depth, true, true));
+}
+
+// With the default limits, failing decodes stay safe on a small stack too,
+// including when a completed value of the maximum depth is dropped on the
+// way out.
+#[test]
+fn test_decode_errors_on_small_stack() {
+    let max_depth = DecodeLimits::default().max_depth;
+    let too_deep = vec![b'l'; 100_000];
+    let mut after_deep_value = vec![b'l'; max_depth];
+    after_deep_value.extend(vec![b'e'; max_depth - 1]);
+    after_deep_value.push(b'x');
+
+    let handle = std::thread::Builder::new()
+        .stack_size(64 * 1024)
+        .spawn(move || {
+            let options = DecodeOptions::default();
+            let too_deep = decode_with(&mut too_deep.into_iter(), &options).map_err(|err| err.kind);
+            let after_deep_value = decode_with(&mut after_deep_value.into_iter(), &options).map_err(|err| err.kind);
+            (too_deep, after_deep_value)
+        })
+        .unwrap();
+
+    let (too_deep, after_deep_value) = handle.join().unwrap();
+    assert_eq!(too_deep, Err(DecodeErrorKind::DepthLimitExceeded));
+    assert_eq!(after_deep_value, Err(DecodeErrorKind::InvalidStartByte));
+}
+
+#[test]
+fn test_decode_deeply_nested_dictionaries() {
+    let depth = 1_000;
+    let mut bytes = "d1:k".repeat(depth).into_bytes();
+    bytes.extend("i1e".bytes());
+    bytes.extend(vec![b'e'; depth]);
+
+    let options = DecodeOptions::default().with_limits(DecodeLimits { max_depth: depth, ..DecodeLimits::default() });
+    let res = decode_with(&mut bytes.clone().into_iter(), &options);
+    assert_eq!(res.is_ok(), true);
+    assert_eq!(encode(&res.ok().unwrap()), bytes);
+}
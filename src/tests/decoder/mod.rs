@@ -1,4 +1,15 @@
 mod bytestring;
 mod dictionary;
 mod integer;
+mod lenient;
+mod limits;
 mod list;
+mod prefix;
+mod push;
+mod reader;
+mod slice;
+mod spanned;
+mod stream;
+mod strict;
+mod tokenizer;
+mod type_ref;
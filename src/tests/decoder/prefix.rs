@@ -0,0 +1,81 @@
+use std::collections::BTreeMap;
+
+use crate::errors::DecodeErrorKind;
+use crate::options::{DecodeLimits, DecodeOptions};
+use crate::protocol::decode_prefix;
+use crate::{Decoder, Type};
+
+
+#[test]
+fn test_decode_prefix_whole_input() {
+    let res = decode_prefix(b"li1e4:spame", &DecodeOptions::default());
+    assert_eq!(res.ok(), Some((Type::List(vec![Type::Integer(1), Type::ByteString(b"spam".to_vec())]), 11)));
+}
+
+#[test]
+fn test_decode_prefix_ut_metadata() {
+    let mut message = b"d8:msg_typei1e5:piecei0e10:total_sizei8ee".to_vec();
+    message.extend_from_slice(&[0xde, 0xad, 0xbe, 0xef, b'i', b'e', 0x00, 0x01]);
+
+    let (t, consumed) = decode_prefix(&message, &DecodeOptions::default()).unwrap();
+
+    let mut d = BTreeMap::new();
+    d.insert(b"msg_type".to_vec(), Type::Integer(1));
+    d.insert(b"piece".to_vec(), Type::Integer(0));
+    d.insert(b"total_size".to_vec(), Type::Integer(8));
+    assert_eq!(t, Type::Dictionary(d));
+    assert_eq!(&message[consumed..], &[0xde, 0xad, 0xbe, 0xef, b'i', b'e', 0x00, 0x01]);
+}
+
+#[test]
+fn test_decode_prefix_consecutive_values() {
+    let bytes = b"i1e3:abcle";
+    let mut rest = &bytes[..];
+    let mut values = vec![];
+
+    while !rest.is_empty() {
+        let (t, consumed) = decode_prefix(rest, &DecodeOptions::default()).unwrap();
+        values.push(t);
+        rest = &rest[consumed..];
+    }
+
+    assert_eq!(values, vec![Type::Integer(1), Type::ByteString(b"abc".to_vec()), Type::List(vec![])]);
+}
+
+#[test]
+fn test_decode_prefix_empty() {
+    let err = decode_prefix(b"", &DecodeOptions::default()).err().unwrap();
+    assert_eq!(err.kind, DecodeErrorKind::Empty);
+}
+
+#[test]
+fn test_decode_prefix_invalid_value() {
+    let err = decode_prefix(b"d3:fooxe", &DecodeOptions::default()).err().unwrap();
+    assert_eq!(err.to_string(), "invalid start byte at byte 6 in foo");
+}
+
+#[test]
+fn test_decode_prefix_strict() {
+    let res = decode_prefix(b"d1:bi1e1:ai2eeXYZ", &DecodeOptions::strict());
+    assert_eq!(res.is_err(), true);
+
+    let res = decode_prefix(b"d1:ai2e1:bi1eeXYZ", &DecodeOptions::strict());
+    assert_eq!(res.map(|(_, consumed)| consumed).ok(), Some(14));
+}
+
+#[test]
+fn test_decode_prefix_limits_apply_to_value_only() {
+    let options = DecodeOptions::default().with_limits(DecodeLimits { max_input_size: 4, ..DecodeLimits::default() });
+
+    let res = decode_prefix(b"i42e and then a lot of trailing data", &options);
+    assert_eq!(res.ok(), Some((Type::Integer(42), 4)));
+}
+
+#[test]
+fn test_decoder_decode_prefix() {
+    let bytes = b"4:spam\x00\x01";
+
+    let (t, consumed) = Decoder::decode_prefix(bytes).unwrap();
+    assert_eq!(t, Type::ByteString(b"spam".to_vec()));
+    assert_eq!(&bytes[consumed..], b"\x00\x01");
+}
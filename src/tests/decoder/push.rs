@@ -0,0 +1,174 @@
+use crate::errors::DecodeErrorKind;
+use crate::options::{DecodeLimits, DecodeOptions};
+use crate::protocol::{decode_prefix, encode};
+use crate::{Progress, PushParser, Type};
+
+
+// Feeds `bytes` in chunks of `chunk` bytes and returns the first complete
+// value with the total number of bytes it took, `None` if more input is
+// needed, or the error message.
+fn feed_chunks(parser: &mut PushParser, bytes: &[u8], chunk: usize) -> Result<Option<(Type, usize)>, String> {
+    let mut fed = 0;
+
+    for piece in bytes.chunks(chunk) {
+        match parser.feed(piece).map_err(|err| err.to_string())? {
+            Progress::NeedMore => fed += piece.len(),
+            Progress::Complete(t, consumed) => return Ok(Some((t, fed + consumed))),
+        }
+    }
+
+    Ok(None)
+}
+
+#[test]
+fn test_push_matches_prefix_decoding() {
+    let inputs = [
+        "i42e", "i-42e", "i0e", "i-0e", "i007e", "i", "i-e", "i12a", "0:", "4:spam", "04:spam", "5:spam",
+        "le", "li1e3:abce", "l", "de", "d1:ai1e1:bli2eee", "d1:bi1e1:ai2ee", "d1:ai1e", "di1ei2ee", "d1:ae",
+        "d1:ai1eldee", "i1ei2e", "4:spamtrailing", "x", "e", "lxe", "1a:", "99999999999999999999999:",
+    ];
+
+    for input in inputs {
+        for options in [DecodeOptions::default(), DecodeOptions::strict(), DecodeOptions::lenient()] {
+            let expected = match decode_prefix(input.as_bytes(), &options) {
+                Ok(res) => Ok(Some(res)),
+                Err(err) if err.offset == input.len() => Ok(None),
+                Err(err) => Err(err.to_string()),
+            };
+
+            for chunk in [1, 2, 3, 100] {
+                let res = feed_chunks(&mut PushParser::with_options(&options), input.as_bytes(), chunk);
+                assert_eq!(res, expected, "input {:?}, chunk {}", input, chunk);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_push_binary_string_across_chunks() {
+    let t = Type::List(vec![Type::ByteString((0..=255u8).cycle().take(5000).collect()), Type::Integer(-1)]);
+    let bytes = encode(&t);
+
+    for chunk in [1, 7, 1000, 4096] {
+        let res = feed_chunks(&mut PushParser::new(), &bytes, chunk);
+        assert_eq!(res, Ok(Some((t.clone(), bytes.len()))));
+    }
+}
+
+#[test]
+fn test_push_consecutive_values() {
+    let bytes = b"d1:y1:qei42e4:spamle";
+    let mut parser = PushParser::new();
+    let mut values = vec![];
+    let mut rest = &bytes[..];
+
+    while !rest.is_empty() {
+        match parser.feed(&rest[..rest.len().min(3)]).unwrap() {
+            Progress::NeedMore => rest = &rest[rest.len().min(3)..],
+            Progress::Complete(t, consumed) => {
+                values.push(t);
+                rest = &rest[consumed..];
+            },
+        }
+    }
+
+    assert_eq!(values.len(), 4);
+    assert_eq!(values[1], Type::Integer(42));
+    assert_eq!(values[3], Type::List(vec![]));
+    assert_eq!(parser.is_empty(), true);
+}
+
+#[test]
+fn test_push_error_offset_in_stream() {
+    let mut parser = PushParser::new();
+
+    assert_eq!(parser.feed(b"i1ei2").ok(), Some(Progress::Complete(Type::Integer(1), 3)));
+
+    let err = parser.feed(b"i2x").err().unwrap();
+    assert_eq!(err.to_string(), "invalid end byte for type: integer at byte 5");
+}
+
+#[test]
+fn test_push_error_path() {
+    let mut parser = PushParser::new();
+
+    assert_eq!(parser.feed(b"d4:info").ok(), Some(Progress::NeedMore));
+    assert_eq!(parser.feed(b"d3:bad").ok(), Some(Progress::NeedMore));
+
+    let err = parser.feed(b"x").err().unwrap();
+    assert_eq!(err.to_string(), "invalid start byte at byte 13 in info.bad");
+}
+
+#[test]
+fn test_push_starts_over_after_error() {
+    let mut parser = PushParser::new();
+
+    assert_eq!(parser.feed(b"lx").is_err(), true);
+    assert_eq!(parser.is_empty(), true);
+    assert_eq!(parser.feed(b"i7e").ok(), Some(Progress::Complete(Type::Integer(7), 3)));
+}
+
+#[test]
+fn test_push_depth_limit() {
+    let options = DecodeOptions::default().with_limits(DecodeLimits { max_depth: 3, ..DecodeLimits::unlimited() });
+    let mut parser = PushParser::with_options(&options);
+
+    assert_eq!(parser.feed(b"lll").ok(), Some(Progress::NeedMore));
+
+    let err = parser.feed(b"l").err().unwrap();
+    assert_eq!(err.kind, DecodeErrorKind::DepthLimitExceeded);
+}
+
+#[test]
+fn test_push_string_length_limit() {
+    let options = DecodeOptions::default().with_limits(DecodeLimits { max_string_length: 8, ..DecodeLimits::unlimited() });
+    let mut parser = PushParser::with_options(&options);
+
+    let err = parser.feed(b"1000000:").err().unwrap();
+    assert_eq!(err.kind, DecodeErrorKind::StringLengthLimitExceeded);
+}
+
+#[test]
+fn test_push_input_size_limit() {
+    let options = DecodeOptions::default().with_limits(DecodeLimits { max_input_size: 16, ..DecodeLimits::unlimited() });
+    let mut parser = PushParser::with_options(&options);
+
+    assert_eq!(parser.feed(b"l4:spam").ok(), Some(Progress::NeedMore));
+
+    let err = parser.feed(b"4:spam4:spam").err().unwrap();
+    assert_eq!(err.kind, DecodeErrorKind::InputSizeLimitExceeded);
+}
+
+#[test]
+fn test_push_min_remaining_never_overshoots() {
+    let bytes = b"d4:infod6:lengthi42e4:listli1e0:eee3:keyle4:spam";
+    let (_, value_len) = decode_prefix(bytes, &DecodeOptions::default()).unwrap();
+    let mut parser = PushParser::new();
+    let mut fed = 0;
+
+    loop {
+        let len = parser.min_remaining();
+        assert_eq!(len >= 1, true);
+        assert_eq!(fed + len <= value_len, true, "overshoot after {} bytes", fed);
+
+        match parser.feed(&bytes[fed..fed + len]).unwrap() {
+            Progress::NeedMore => fed += len,
+            Progress::Complete(_, consumed) => {
+                assert_eq!(fed + consumed, value_len);
+                break;
+            },
+        }
+    }
+}
+
+#[test]
+fn test_push_finish() {
+    let mut parser = PushParser::new();
+    assert_eq!(parser.finish().is_ok(), true);
+
+    assert_eq!(parser.feed(b"d3:foo").ok(), Some(Progress::NeedMore));
+
+    let err = parser.finish().err().unwrap();
+    assert_eq!(err.to_string(), "invalid dictionary at byte 6");
+    assert_eq!(parser.is_empty(), true);
+}
@@ -0,0 +1,151 @@
+use std::io::{self, Read};
+
+use crate::errors::DecodeErrorKind;
+use crate::options::{DecodeLimits, DecodeOptions};
+use crate::protocol::{decode_reader, encode};
+use crate::{DecodeError, Decoder, Type};
+
+
+// Yields `data` in chunks of `chunk` bytes and then fails with `error`
+// instead of reporting EOF.
+pub(super) struct FailingReader {
+    data: Vec<u8>,
+    pos: usize,
+    chunk: usize,
+    error: Option<io::ErrorKind>,
+}
+
+impl FailingReader {
+    pub(super) fn new(data: &[u8], chunk: usize, error: Option<io::ErrorKind>) -> FailingReader {
+        FailingReader { data: data.to_vec(), pos: 0, chunk, error }
+    }
+}
+
+impl Read for FailingReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos == self.data.len() {
+            return match self.error {
+                Some(kind) => Err(io::Error::new(kind, "disk on fire")),
+                None => Ok(0),
+            };
+        }
+
+        let n = self.chunk.min(buf.len()).min(self.data.len() - self.pos);
+        buf[..n].copy_from_slice(&self.data[self.pos..self.pos + n]);
+        self.pos += n;
+
+        Ok(n)
+    }
+}
+
+// Fails with `Interrupted` before every successful read.
+struct InterruptingReader {
+    inner: FailingReader,
+    interrupt: bool,
+}
+
+impl Read for InterruptingReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.interrupt = !self.interrupt;
+        match self.interrupt {
+            true => Err(io::Error::new(io::ErrorKind::Interrupted, "interrupted")),
+            false => self.inner.read(buf),
+        }
+    }
+}
+
+fn torrent() -> Type {
+    let info = Type::Dictionary([
+        (b"length".to_vec(), Type::Integer(1024)),
+        (b"name".to_vec(), Type::ByteString(b"file.bin".to_vec())),
+        (b"pieces".to_vec(), Type::ByteString((0..=255u8).collect())),
+    ].into_iter().collect());
+
+    Type::Dictionary([
+        (b"announce".to_vec(), Type::ByteString(b"http://tracker".to_vec())),
+        (b"info".to_vec(), info),
+    ].into_iter().collect())
+}
+
+#[test]
+fn test_decode_reader() {
+    let t = torrent();
+    let bytes = encode(&t);
+
+    for chunk in [1, 3, 7, 64, 8192] {
+        let res = decode_reader(FailingReader::new(&bytes, chunk, None), &DecodeOptions::default());
+        assert_eq!(res.ok(), Some(t.clone()));
+    }
+}
+
+#[test]
+fn test_decode_reader_retries_interrupted() {
+    let t = torrent();
+    let bytes = encode(&t);
+    let reader = InterruptingReader { inner: FailingReader::new(&bytes, 5, None), interrupt: false };
+
+    let res = decode_reader(reader, &DecodeOptions::default());
+    assert_eq!(res.ok(), Some(t));
+}
+
+#[test]
+fn test_decode_reader_error_midway() {
+    let bytes = encode(&torrent());
+
+    for cut in [1, 10, 20, bytes.len() / 2, bytes.len() - 1] {
+        let reader = FailingReader::new(&bytes[..cut], 4, Some(io::ErrorKind::ConnectionReset));
+
+        let err = decode_reader(reader, &DecodeOptions::default()).err().unwrap();
+        assert_eq!(err.kind, DecodeErrorKind::Io { kind: io::ErrorKind::ConnectionReset, message: "disk on fire".to_string() });
+        assert_eq!(err.offset, cut);
+    }
+}
+
+#[test]
+fn test_decode_reader_error_inside_byte_string() {
+    let reader = FailingReader::new(b"d4:name10:abc", 2, Some(io::ErrorKind::TimedOut));
+
+    let err = decode_reader(reader, &DecodeOptions::default()).err().unwrap();
+    assert_eq!(err.to_string(), "I/O error: disk on fire at byte 13 in name");
+}
+
+#[test]
+fn test_decode_reader_error_after_value() {
+    let reader = FailingReader::new(b"i42e", 2, Some(io::ErrorKind::BrokenPipe));
+
+    let err = decode_reader(reader, &DecodeOptions::default()).err().unwrap();
+    assert_eq!(err.kind, DecodeErrorKind::Io { kind: io::ErrorKind::BrokenPipe, message: "disk on fire".to_string() });
+    assert_eq!(err.offset, 4);
+}
+
+#[test]
+fn test_decode_reader_truncated() {
+    let reader = FailingReader::new(b"li1e", 2, None);
+
+    let err = decode_reader(reader, &DecodeOptions::default()).err().unwrap();
+    assert_eq!(err.kind, DecodeErrorKind::InvalidList);
+}
+
+#[test]
+fn test_decode_reader_limits() {
+    let bytes = encode(&torrent());
+    let options = DecodeOptions::default().with_limits(DecodeLimits { max_input_size: 32, ..DecodeLimits::default() });
+
+    let err = decode_reader(FailingReader::new(&bytes, 4, None), &options).err().unwrap();
+    assert_eq!(err.kind, DecodeErrorKind::InputSizeLimitExceeded);
+}
+
+#[test]
+fn test_decoder_decode_reader_typed_error() {
+    let reader = FailingReader::new(b"l4:spam", 3, Some(io::ErrorKind::Other));
+
+    let err = Decoder::decode_reader(reader).err().unwrap();
+    let err = err.downcast_ref::<DecodeError>().unwrap();
+    assert_eq!(err.kind, DecodeErrorKind::Io { kind: io::ErrorKind::Other, message: "disk on fire".to_string() });
+}
+
+#[test]
+fn test_decoder_decode_from_missing_file() {
+    let res = Decoder::decode_from("/nonexistent/file.torrent");
+    assert_eq!(res.is_err(), true);
+}
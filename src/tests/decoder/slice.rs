@@ -0,0 +1,88 @@
+use crate::options::{DecodeLimits, DecodeOptions};
+use crate::protocol::{decode_slice, decode_with, encode};
+use crate::Type;
+
+
+fn assert_same_as_iterator(bytes: &[u8], options: &DecodeOptions) {
+    let from_slice = decode_slice(bytes, options);
+    let from_iter = decode_with(&mut bytes.iter().copied(), options);
+
+    match (from_slice, from_iter) {
+        (Ok(a), Ok(b)) => assert_eq!(a, b),
+        (Err(a), Err(b)) => assert_eq!(a.to_string(), b.to_string()),
+        (a, b) => panic!("slice: {:?}, iterator: {:?}", a, b),
+    }
+}
+
+#[test]
+fn test_decode_slice_matches_iterator() {
+    let inputs = [
+        "i42e", "i-42e", "i0e", "i-0e", "i007e", "i", "i-e", "i12a", "0:", "4:spam", "04:spam", "5:spam",
+        "le", "li1e3:abce", "l", "de", "d1:ai1e1:bli2eee", "d1:bi1e1:ai2ee", "d1:ai1e", "di1ei2ee",
+        "i1ei2e", "x", "",
+    ];
+
+    for input in inputs {
+        assert_same_as_iterator(input.as_bytes(), &DecodeOptions::default());
+        assert_same_as_iterator(input.as_bytes(), &DecodeOptions::strict());
+        assert_same_as_iterator(input.as_bytes(), &DecodeOptions::lenient());
+    }
+}
+
+#[test]
+fn test_decode_slice_binary_string() {
+    let t = Type::List(vec![Type::ByteString((0..=255u8).collect()), Type::Integer(1)]);
+    let bytes = encode(&t);
+
+    let res = decode_slice(&bytes, &DecodeOptions::default());
+    assert_eq!(res.ok(), Some(t));
+}
+
+#[test]
+fn test_decode_slice_integer_bounds() {
+    let bytes = format!("li{}ei{}ee", i64::MIN, i64::MAX).into_bytes();
+
+    let res = decode_slice(&bytes, &DecodeOptions::default());
+    assert_eq!(res.ok(), Some(Type::List(vec![Type::Integer(i64::MIN), Type::Integer(i64::MAX)])));
+}
+
+#[test]
+fn test_decode_slice_integer_overflow() {
+    let bytes = format!("i{}0e", i64::MAX).into_bytes();
+
+    let res = decode_slice(&bytes, &DecodeOptions::default());
+    assert_eq!(res.is_err(), true);
+
+    let err = res.err().unwrap();
+    assert_eq!(err.to_string(), "invalid integer at byte 0");
+}
+
+#[test]
+fn test_decode_slice_length_larger_than_input() {
+    let bytes = "l18446744073709551615:abce".bytes().collect::<Vec<u8>>();
+
+    let res = decode_slice(&bytes, &DecodeOptions::default());
+    assert_eq!(res.is_err(), true);
+
+    let err = res.err().unwrap();
+    assert_eq!(err.to_string(), "invalid byte string length at byte 26 in [0]");
+}
+
+#[test]
+fn test_decode_slice_input_size_limit() {
+    let limits = DecodeLimits { max_input_size: 8, ..DecodeLimits::unlimited() };
+    let options = DecodeOptions::default().with_limits(limits);
+
+    assert_same_as_iterator(b"l3:abc3:defe", &options);
+    assert_same_as_iterator(b"10:abcdefgh", &options);
+    assert_same_as_iterator(b"6:abcdef", &options);
+    assert_same_as_iterator(b"7:abcdef", &options);
+}
+
+#[test]
+fn test_decode_slice_string_length_limit() {
+    let options = DecodeOptions::default().with_limits(DecodeLimits { max_string_length: 3, ..DecodeLimits::unlimited() });
+
+    assert_same_as_iterator(b"4:spam", &options);
+    assert_same_as_iterator(b"l3:abc4:spame", &options);
+}
@@ -0,0 +1,82 @@
+use crate::options::DecodeOptions;
+use crate::protocol::{decode_slice, decode_slice_spanned};
+use crate::{Decoder, SpannedType, Type};
+
+
+#[test]
+fn test_decode_spanned_ranges() {
+    let bytes = b"d8:announce3:url4:infod6:lengthi3e4:name3:abcee";
+
+    let t = Decoder::decode_spanned(bytes).unwrap();
+    assert_eq!(t.span, 0..bytes.len());
+    assert_eq!(t.get("announce").unwrap().span, 11..16);
+
+    let info = t.get("info").unwrap();
+    assert_eq!(info.span, 22..46);
+    assert_eq!(info.slice(bytes), b"d6:lengthi3e4:name3:abce");
+    assert_eq!(info.get("length").unwrap().span, 31..34);
+    assert_eq!(info.get("length").unwrap().value, SpannedType::Integer(3));
+    assert_eq!(info.get("name").unwrap().slice(bytes), b"3:abc");
+}
+
+#[test]
+fn test_decode_spanned_key_ranges() {
+    let bytes = b"d8:announce3:url4:infod6:lengthi3eee";
+
+    let t = Decoder::decode_spanned(bytes).unwrap();
+    assert_eq!(t.key_span, None);
+    assert_eq!(t.get("announce").unwrap().key_span, Some(1..11));
+
+    let info = t.get("info").unwrap();
+    assert_eq!(info.key_span, Some(16..22));
+    assert_eq!(&bytes[info.get("length").unwrap().key_span.clone().unwrap()], b"6:length");
+
+    let t = Decoder::decode_spanned(b"li1ee").unwrap();
+    assert_eq!(t.index(0).unwrap().key_span, None);
+
+    // The last of duplicate keys is the one whose value is kept.
+    let t = Decoder::decode_spanned_with(b"d1:ai1e1:ai2ee", &DecodeOptions::lenient()).unwrap();
+    assert_eq!(t.get("a").unwrap().key_span, Some(7..10));
+    assert_eq!(t.get("a").unwrap().value, SpannedType::Integer(2));
+}
+
+#[test]
+fn test_decode_spanned_list() {
+    let bytes = b"li1ele4:spame";
+
+    let t = Decoder::decode_spanned(bytes).unwrap();
+    assert_eq!(t.index(0).unwrap().span, 1..4);
+    assert_eq!(t.index(1).unwrap().slice(bytes), b"le");
+    assert_eq!(t.index(2).unwrap().span, 6..12);
+    assert_eq!(t.index(3).is_err(), true);
+    assert_eq!(t.get("a").is_err(), true);
+}
+
+#[test]
+fn test_decode_spanned_unsorted_keys() {
+    // Lenient decoding keeps the spans of the input, not of the canonical
+    // encoding.
+    let bytes = b"d1:bi1e1:ai22ee";
+
+    let t = Decoder::decode_spanned_with(bytes, &DecodeOptions::lenient()).unwrap();
+    assert_eq!(t.get("a").unwrap().span, 10..14);
+    assert_eq!(t.get("b").unwrap().span, 4..7);
+}
+
+#[test]
+fn test_decode_spanned_matches_owned() {
+    let inputs = ["i42e", "i-0e", "4:spam", "li1e3:abce", "d1:ai1e1:bli2eee", "d1:bi1e1:ai2ee", "i1ei2e", ""];
+
+    for input in inputs {
+        for options in [DecodeOptions::default(), DecodeOptions::strict(), DecodeOptions::lenient()] {
+            let owned = decode_slice(input.as_bytes(), &options);
+            let spanned = decode_slice_spanned(input.as_bytes(), &options);
+
+            match (owned, spanned) {
+                (Ok(a), Ok(b)) => assert_eq!(a, Type::from(b)),
+                (Err(a), Err(b)) => assert_eq!(a.to_string(), b.to_string()),
+                (a, b) => panic!("owned: {:?}, spanned: {:?}", a, b),
+            }
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use std::io;
+
+use super::reader::FailingReader;
+use crate::errors::DecodeErrorKind;
+use crate::options::{DecodeLimits, DecodeOptions};
+use crate::protocol::StreamDecoder;
+use crate::{DecodeError, Decoder, Type};
+
+
+fn collect(bytes: &[u8], chunk: usize) -> Vec<Result<(usize, Type), String>> {
+    Decoder::stream(FailingReader::new(bytes, chunk, None))
+        .map(|item| item.map_err(|err| err.to_string()))
+        .collect()
+}
+
+#[test]
+fn test_stream_values_with_offsets() {
+    let bytes = b"d1:y1:qei42e4:spamli1ee";
+    let expected = vec![
+        Ok((0, Type::Dictionary([(b"y".to_vec(), Type::ByteString(b"q".to_vec()))].into_iter().collect()))),
+        Ok((8, Type::Integer(42))),
+        Ok((12, Type::ByteString(b"spam".to_vec()))),
+        Ok((18, Type::List(vec![Type::Integer(1)]))),
+    ];
+
+    for chunk in [1, 2, 5, 64] {
+        assert_eq!(collect(bytes, chunk), expected);
+    }
+}
+
+#[test]
+fn test_stream_empty() {
+    assert_eq!(collect(b"", 4), vec![]);
+}
+
+#[test]
+fn test_stream_truncated_value() {
+    let res = collect(b"i1e3:abcd1:ai", 3);
+
+    assert_eq!(res.len(), 3);
+    assert_eq!(res[1], Ok((3, Type::ByteString(b"abc".to_vec()))));
+    assert_eq!(res[2], Err("invalid integer at byte 13 in a".to_string()));
+}
+
+#[test]
+fn test_stream_truncated_integer() {
+    assert_eq!(collect(b"i1ei42", 2), vec![Ok((0, Type::Integer(1))), Err("invalid integer at byte 6".to_string())]);
+}
+
+#[test]
+fn test_stream_truncated_byte_string() {
+    assert_eq!(collect(b"4:spam4:spa", 2), vec![
+        Ok((0, Type::ByteString(b"spam".to_vec()))),
+        Err("invalid byte string length at byte 11".to_string()),
+    ]);
+}
+
+#[test]
+fn test_stream_stops_after_error() {
+    let mut stream = Decoder::stream(&b"i1exi2e"[..]);
+
+    assert_eq!(stream.next().unwrap().ok(), Some((0, Type::Integer(1))));
+    assert_eq!(stream.next().unwrap().is_err(), true);
+    assert_eq!(stream.next().is_none(), true);
+}
+
+#[test]
+fn test_stream_io_error() {
+    let mut stream = Decoder::stream(FailingReader::new(b"i1ei2", 2, Some(io::ErrorKind::ConnectionAborted)));
+
+    assert_eq!(stream.next().unwrap().ok(), Some((0, Type::Integer(1))));
+
+    let err = stream.next().unwrap().err().unwrap();
+    let err = err.downcast_ref::<DecodeError>().unwrap();
+    assert_eq!(err.kind, DecodeErrorKind::Io { kind: io::ErrorKind::ConnectionAborted, message: "disk on fire".to_string() });
+    assert_eq!(err.offset, 5);
+    assert_eq!(stream.next().is_none(), true);
+}
+
+#[test]
+fn test_stream_io_error_between_values() {
+    let mut stream = Decoder::stream(FailingReader::new(b"i1e", 2, Some(io::ErrorKind::ConnectionAborted)));
+
+    assert_eq!(stream.next().unwrap().ok(), Some((0, Type::Integer(1))));
+    assert_eq!(stream.next().unwrap().is_err(), true);
+}
+
+#[test]
+fn test_stream_limits_apply_per_value() {
+    let limits = DecodeLimits { max_input_size: 4, ..DecodeLimits::default() };
+    let options = DecodeOptions::default().with_limits(limits);
+    let mut decoder = StreamDecoder::new(&b"i10ei20ei300e"[..], options);
+
+    assert_eq!(decoder.next_value().ok(), Some(Some((0, Type::Integer(10)))));
+    assert_eq!(decoder.next_value().ok(), Some(Some((4, Type::Integer(20)))));
+
+    let err = decoder.next_value().err().unwrap();
+    assert_eq!(err.kind, DecodeErrorKind::InputSizeLimitExceeded);
+    assert_eq!(err.offset, 12);
+}
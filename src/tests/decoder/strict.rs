@@ -0,0 +1,90 @@
+use crate::options::DecodeOptions;
+use crate::protocol::decode_with;
+use crate::Type;
+
+
+#[test]
+fn test_decode_strict_canonical_dictionary() {
+    let bytes = "d1:ai1e1:bl3:abcee".bytes().collect::<Vec<u8>>();
+
+    let res = decode_with(&mut bytes.into_iter(), &DecodeOptions::strict());
+    assert_eq!(res.is_ok(), true);
+
+    match &res.ok().unwrap() {
+        Type::Dictionary(d) => assert_eq!(d.len(), 2),
+        _ => panic!("error"),
+    }
+}
+
+#[test]
+fn test_decode_strict_unsorted_keys() {
+    let bytes = "d1:bi1e1:ai2ee".bytes().collect::<Vec<u8>>();
+
+    let res = decode_with(&mut bytes.into_iter(), &DecodeOptions::strict());
+    assert_eq!(res.is_err(), true);
+
+    let err = res.err().unwrap();
+    assert_eq!(err.to_string(), "dictionary keys are not sorted at byte 7");
+}
+
+#[test]
+fn test_decode_strict_unsorted_nested_keys() {
+    let bytes = "ld1:ai1eed2:k2i1e2:k1i2eee".bytes().collect::<Vec<u8>>();
+
+    let res = decode_with(&mut bytes.into_iter(), &DecodeOptions::strict());
+    assert_eq!(res.is_err(), true);
+
+    let err = res.err().unwrap();
+    assert_eq!(err.to_string(), "dictionary keys are not sorted at byte 17 in [1]");
+}
+
+#[test]
+fn test_decode_strict_duplicate_keys() {
+    let bytes = "d1:ai1e1:ai2ee".bytes().collect::<Vec<u8>>();
+
+    let res = decode_with(&mut bytes.into_iter(), &DecodeOptions::strict());
+    assert_eq!(res.is_err(), true);
+
+    let err = res.err().unwrap();
+    assert_eq!(err.to_string(), "duplicate dictionary key at byte 7");
+}
+
+#[test]
+fn test_decode_strict_length_with_leading_zeros() {
+    let bytes = "03:abc".bytes().collect::<Vec<u8>>();
+
+    let res = decode_with(&mut bytes.into_iter(), &DecodeOptions::strict());
+    assert_eq!(res.is_err(), true);
+
+    let err = res.err().unwrap();
+    assert_eq!(err.to_string(), "byte string length with leading zeros is not allowed at byte 0");
+}
+
+#[test]
+fn test_decode_strict_zero_length() {
+    let bytes = "0:".bytes().collect::<Vec<u8>>();
+
+    let res = decode_with(&mut bytes.into_iter(), &DecodeOptions::strict());
+    assert_eq!(res.is_ok(), true);
+
+    match &res.ok().unwrap() {
+        Type::ByteString(s) => assert_eq!(s.len(), 0),
+        _ => panic!("error"),
+    }
+}
+
+#[test]
+fn test_decode_standard_accepts_non_canonical() {
+    let bytes = "d1:bi1e1:bi3e1:a03:abce".bytes().collect::<Vec<u8>>();
+
+    let res = decode_with(&mut bytes.into_iter(), &DecodeOptions::default());
+    assert_eq!(res.is_ok(), true);
+
+    match &res.ok().unwrap() {
+        Type::Dictionary(d) => {
+            assert_eq!(d.get(b"a".as_slice()), Some(&Type::ByteString(b"abc".to_vec())));
+            assert_eq!(d.get(b"b".as_slice()), Some(&Type::Integer(3)));
+        },
+        _ => panic!("error"),
+    }
+}
@@ -0,0 +1,139 @@
+use std::collections::BTreeMap;
+use std::io;
+use std::iter::Peekable;
+use std::vec::IntoIter;
+
+use super::reader::FailingReader;
+use crate::errors::DecodeErrorKind;
+use crate::options::{DecodeLimits, DecodeOptions};
+use crate::protocol::{decode_with, encode, Event, Tokenizer};
+use crate::warning::DecodeWarningKind;
+use crate::Type;
+
+
+// Rebuilds a value from its tokens, to compare the tokenizer with the decoder.
+fn build(tokens: &mut Peekable<IntoIter<Event>>) -> Type {
+    match tokens.next().unwrap() {
+        Event::Integer(i) => Type::Integer(i),
+        Event::Bytes(b) => Type::ByteString(b),
+        Event::ListStart => {
+            let mut l = vec![];
+            while tokens.next_if_eq(&Event::End).is_none() {
+                l.push(build(tokens));
+            }
+            Type::List(l)
+        },
+        Event::DictStart => {
+            let mut d = BTreeMap::new();
+            while let Some(Event::Key(k)) = tokens.next() {
+                d.insert(k, build(tokens));
+            }
+            Type::Dictionary(d)
+        },
+        event => panic!("unexpected {:?}", event),
+    }
+}
+
+fn tokenize(bytes: &[u8], options: &DecodeOptions) -> Result<Type, String> {
+    let events = Tokenizer::with_options(bytes, options)
+        .map(|token| token.map(|(_, event)| event))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| err.to_string())?;
+
+    let mut events = events.into_iter().peekable();
+    let t = build(&mut events);
+    assert_eq!(events.next(), None);
+
+    Ok(t)
+}
+
+#[test]
+fn test_tokenizer_events_and_offsets() {
+    let tokens = Tokenizer::new(&b"d4:infod6:lengthi7ee4:listli1e0:ee"[..]).collect::<Result<Vec<_>, _>>().unwrap();
+
+    assert_eq!(tokens, vec![
+        (0, Event::DictStart),
+        (1, Event::Key(b"info".to_vec())),
+        (7, Event::DictStart),
+        (8, Event::Key(b"length".to_vec())),
+        (16, Event::Integer(7)),
+        (19, Event::End),
+        (20, Event::Key(b"list".to_vec())),
+        (26, Event::ListStart),
+        (27, Event::Integer(1)),
+        (30, Event::Bytes(vec![])),
+        (32, Event::End),
+        (33, Event::End),
+    ]);
+}
+
+#[test]
+fn test_tokenizer_matches_decoder() {
+    let inputs = [
+        "i42e", "i-42e", "i-0e", "i007e", "i", "i12a", "0:", "4:spam", "04:spam", "5:spam", "le", "li1e3:abce",
+        "l", "de", "d1:ai1e1:bli2eee", "d1:bi1e1:ai2ee", "d1:ai1e1:ai2ee", "d1:ai1e", "di1ei2ee", "d1:ae",
+        "d1:ad1:bi1xeee", "lli1eei2ee", "i1ei2e", "x", "", "e",
+    ];
+
+    for input in inputs {
+        for options in [DecodeOptions::default(), DecodeOptions::strict(), DecodeOptions::lenient()] {
+            let expected = decode_with(&mut input.bytes(), &options).map_err(|err| err.to_string());
+            assert_eq!(tokenize(input.as_bytes(), &options), expected, "input {:?}", input);
+        }
+    }
+}
+
+#[test]
+fn test_tokenizer_large_value() {
+    let t = Type::List((0..1000).map(|i| Type::List(vec![Type::Integer(i), Type::ByteString(vec![i as u8; 300])])).collect());
+
+    assert_eq!(tokenize(&encode(&t), &DecodeOptions::default()), Ok(t));
+}
+
+#[test]
+fn test_tokenizer_lenient_warnings() {
+    let mut tokenizer = Tokenizer::with_options(&b"d1:bi1e1:ai2eeXX"[..], &DecodeOptions::lenient());
+
+    assert_eq!(tokenizer.by_ref().count(), 6);
+
+    let kinds = tokenizer.warnings().iter().map(|w| (w.kind, w.offset)).collect::<Vec<_>>();
+    assert_eq!(kinds, vec![(DecodeWarningKind::UnsortedDictionaryKeys, 7), (DecodeWarningKind::TrailingBytes, 14)]);
+}
+
+#[test]
+fn test_tokenizer_error_path() {
+    let err = Tokenizer::new(&b"d5:filesld6:lengthi1eed6:lengthi-eeee"[..]).find_map(Result::err).unwrap();
+    assert_eq!(err.to_string(), "invalid integer at byte 31 in files[1].length");
+}
+
+#[test]
+fn test_tokenizer_stops_after_error() {
+    let mut tokenizer = Tokenizer::new(&b"lxi1ee"[..]);
+
+    assert_eq!(tokenizer.next().unwrap().ok(), Some((0, Event::ListStart)));
+    assert_eq!(tokenizer.next().unwrap().is_err(), true);
+    assert_eq!(tokenizer.next().is_none(), true);
+}
+
+#[test]
+fn test_tokenizer_limits() {
+    let options = DecodeOptions::default().with_limits(DecodeLimits { max_depth: 2, max_items: 2, ..DecodeLimits::unlimited() });
+
+    let err = Tokenizer::with_options(&b"llleee"[..], &options).find_map(Result::err).unwrap();
+    assert_eq!(err.kind, DecodeErrorKind::DepthLimitExceeded);
+
+    let err = Tokenizer::with_options(&b"li1ei2ei3ee"[..], &options).find_map(Result::err).unwrap();
+    assert_eq!(err.kind, DecodeErrorKind::ItemLimitExceeded);
+}
+
+#[test]
+fn test_tokenizer_io_error() {
+    let reader = FailingReader::new(b"li1e", 2, Some(io::ErrorKind::TimedOut));
+
+    let tokens = Tokenizer::new(reader).collect::<Vec<_>>();
+    assert_eq!(tokens.len(), 3);
+
+    let err = tokens[2].as_ref().err().unwrap();
+    assert_eq!(err.kind, DecodeErrorKind::Io { kind: io::ErrorKind::TimedOut, message: "disk on fire".to_string() });
+    assert_eq!(err.offset, 4);
+}
@@ -0,0 +1,71 @@
+use std::convert::TryFrom;
+
+use crate::options::{DecodeLimits, DecodeOptions};
+use crate::protocol::{decode_slice, decode_slice_ref, encode};
+use crate::{Type, TypeRef};
+
+
+#[test]
+fn test_decode_ref_borrows_from_input() {
+    let bytes = b"d4:infod6:lengthi3e4:name3:abce4:spam4:eggse".to_vec();
+
+    let t = decode_slice_ref(&bytes, &DecodeOptions::default()).unwrap();
+    let name = t.get("info").unwrap().get("name").unwrap();
+    let name = <&[u8]>::try_from(name).unwrap();
+
+    assert_eq!(name, b"abc");
+    assert_eq!(name.as_ptr(), bytes[27..].as_ptr());
+}
+
+#[test]
+fn test_decode_ref_matches_owned() {
+    let inputs = [
+        "i42e", "i-0e", "4:spam", "5:spam", "li1e3:abce", "d1:ai1e1:bli2eee", "d1:bi1e1:ai2ee", "di1ei2ee",
+        "i1ei2e", "",
+    ];
+
+    for input in inputs {
+        for options in [DecodeOptions::default(), DecodeOptions::strict(), DecodeOptions::lenient()] {
+            let owned = decode_slice(input.as_bytes(), &options);
+            let borrowed = decode_slice_ref(input.as_bytes(), &options);
+
+            match (owned, borrowed) {
+                (Ok(a), Ok(b)) => assert_eq!(a, b.to_type()),
+                (Err(a), Err(b)) => assert_eq!(a.to_string(), b.to_string()),
+                (a, b) => panic!("owned: {:?}, borrowed: {:?}", a, b),
+            }
+        }
+    }
+}
+
+#[test]
+fn test_decode_ref_binary_string() {
+    let t = Type::Dictionary([(vec![0xff, 0x00], Type::ByteString((0..=255u8).collect()))].into_iter().collect());
+    let bytes = encode(&t);
+
+    let res = decode_slice_ref(&bytes, &DecodeOptions::default()).unwrap();
+    assert_eq!(Type::from(&res), t);
+}
+
+#[test]
+fn test_decode_ref_limits() {
+    let options = DecodeOptions::default().with_limits(DecodeLimits { max_string_length: 3, ..DecodeLimits::default() });
+
+    let err = decode_slice_ref(b"4:spam", &options).unwrap_err();
+    assert_eq!(err.to_string(), "byte string length limit exceeded at byte 0");
+}
+
+#[test]
+fn test_type_ref_conversions() {
+    let t = TypeRef::List(vec![TypeRef::Integer(7), TypeRef::ByteString(b"caf\xc3\xa9")]);
+
+    match &t {
+        TypeRef::List(l) => {
+            assert_eq!(i64::try_from(&l[0]).ok(), Some(7));
+            assert_eq!(<&str>::try_from(&l[1]).ok(), Some("café"));
+            assert_eq!(i64::try_from(&l[1]).is_err(), true);
+        },
+        _ => unreachable!(),
+    }
+    assert_eq!(t.get("a").is_err(), true);
+}
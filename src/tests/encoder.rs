@@ -1,7 +1,8 @@
 use std::collections::BTreeMap;
+use std::io::{self, Write};
 
-use crate::protocol::encode;
-use crate::Type;
+use crate::protocol::{encode, encode_into, encoded_len};
+use crate::{Encoder, Type};
 
 
 #[test]
@@ -47,7 +48,7 @@ fn test_encode_negative_lower_bound() {
 #[test]
 fn test_encode_empty_string() {
     let correct = "0:".bytes().collect::<Vec<u8>>();
-    let to_encode = Type::ByteString("".to_string());
+    let to_encode = Type::ByteString(b"".to_vec());
 
     assert_eq!(encode(&to_encode), correct);
 }
@@ -55,7 +56,7 @@ fn test_encode_empty_string() {
 #[test]
 fn test_encode_string() {
     let correct = "10:spamsspams".bytes().collect::<Vec<u8>>();
-    let to_encode = Type::ByteString("spamsspams".to_string());
+    let to_encode = Type::ByteString(b"spamsspams".to_vec());
 
     assert_eq!(encode(&to_encode), correct);
 }
@@ -63,7 +64,7 @@ fn test_encode_string() {
 #[test]
 fn test_encode_string_one_byte() {
     let correct = "1:c".bytes().collect::<Vec<u8>>();
-    let to_encode = Type::ByteString("c".to_string());
+    let to_encode = Type::ByteString(b"c".to_vec());
 
     assert_eq!(encode(&to_encode), correct);
 }
@@ -71,7 +72,7 @@ fn test_encode_string_one_byte() {
 #[test]
 fn test_encode_empty_multiple_bytes() {
     let correct = "26:abcdefghijklmnopqrstuvwxyz".bytes().collect::<Vec<u8>>();
-    let to_encode = Type::ByteString("abcdefghijklmnopqrstuvwxyz".to_string());
+    let to_encode = Type::ByteString(b"abcdefghijklmnopqrstuvwxyz".to_vec());
 
     assert_eq!(encode(&to_encode), correct);
 }
@@ -81,12 +82,21 @@ fn test_encode_string_very_login() {
     let correct = "78:abcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyz"
                             .bytes().collect::<Vec<u8>>();
     let to_encode = Type::ByteString(
-        "abcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyz".to_string()
+        b"abcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyzabcdefghijklmnopqrstuvwxyz".to_vec()
 );
 
     assert_eq!(encode(&to_encode), correct);
 }
 
+#[test]
+fn test_encode_string_binary() {
+    let mut correct = "4:".bytes().collect::<Vec<u8>>();
+    correct.extend_from_slice(&[0x00, 0x80, 0xfe, 0xff]);
+    let to_encode = Type::ByteString(vec![0x00, 0x80, 0xfe, 0xff]);
+
+    assert_eq!(encode(&to_encode), correct);
+}
+
 #[test]
 fn test_encode_list_empty() {
     let correct = "le".bytes().collect::<Vec<u8>>();
@@ -109,9 +119,9 @@ fn test_encode_list_of_integers() {
 fn test_encode_list_of_strings() {
     let correct = "l3:str1:x3:abce".bytes().collect::<Vec<u8>>();
     let l = vec![
-        Type::ByteString("str".to_string()),
-        Type::ByteString("x".to_string()),
-        Type::ByteString("abc".to_string()),
+        Type::ByteString(b"str".to_vec()),
+        Type::ByteString(b"x".to_vec()),
+        Type::ByteString(b"abc".to_vec()),
     ];
     let to_encode = Type::List(l);
 
@@ -122,11 +132,11 @@ fn test_encode_list_of_strings() {
 fn test_encode_list_of_integers_and_strings() {
     let correct = "l3:stri42ei0e1:x3:abci-55ee".bytes().collect::<Vec<u8>>();
     let l = vec![
-        Type::ByteString("str".to_string()),
+        Type::ByteString(b"str".to_vec()),
         Type::Integer(42),
         Type::Integer(0),
-        Type::ByteString("x".to_string()),
-        Type::ByteString("abc".to_string()),
+        Type::ByteString(b"x".to_vec()),
+        Type::ByteString(b"abc".to_vec()),
         Type::Integer(-55),
     ];
     let to_encode = Type::List(l);
@@ -138,14 +148,14 @@ fn test_encode_list_of_integers_and_strings() {
 fn test_encode_list_of_integers_and_strings_large() {
     let correct = "l3:stri33ei0e1:x3:abci-1111e2:ok3:okee".bytes().collect::<Vec<u8>>();
     let l = vec![
-        Type::ByteString("str".to_string()),
+        Type::ByteString(b"str".to_vec()),
         Type::Integer(33),
         Type::Integer(0),
-        Type::ByteString("x".to_string()),
-        Type::ByteString("abc".to_string()),
+        Type::ByteString(b"x".to_vec()),
+        Type::ByteString(b"abc".to_vec()),
         Type::Integer(-1111),
-        Type::ByteString("ok".to_string()),
-        Type::ByteString("oke".to_string()),
+        Type::ByteString(b"ok".to_vec()),
+        Type::ByteString(b"oke".to_vec()),
     ];
     let to_encode = Type::List(l);
 
@@ -171,10 +181,10 @@ fn test_encode_list_of_integers_and_lists() {
 fn test_encode_list_of_strings_and_lists() {
     let correct = "l3:abcl3:def1:3e6:ghimnol0:elee".bytes().collect::<Vec<u8>>();
     let l = vec![
-        Type::ByteString("abc".to_string()),
-        Type::List(vec![Type::ByteString("def".to_string()), Type::ByteString("3".to_string())]),
-        Type::ByteString("ghimno".to_string()),
-        Type::List(vec![Type::ByteString("".to_string())]),
+        Type::ByteString(b"abc".to_vec()),
+        Type::List(vec![Type::ByteString(b"def".to_vec()), Type::ByteString(b"3".to_vec())]),
+        Type::ByteString(b"ghimno".to_vec()),
+        Type::List(vec![Type::ByteString(b"".to_vec())]),
         Type::List(vec![]),
     ];
     let to_encode = Type::List(l);
@@ -186,11 +196,11 @@ fn test_encode_list_of_strings_and_lists() {
 fn test_encode_list_of_integers_strings_and_lists() {
     let correct = "l3:abcl3:def1:3ei2023el0:eli1e4:rustee".bytes().collect::<Vec<u8>>();
     let l = vec![
-        Type::ByteString("abc".to_string()),
-        Type::List(vec![Type::ByteString("def".to_string()), Type::ByteString("3".to_string())]),
+        Type::ByteString(b"abc".to_vec()),
+        Type::List(vec![Type::ByteString(b"def".to_vec()), Type::ByteString(b"3".to_vec())]),
         Type::Integer(2023),
-        Type::List(vec![Type::ByteString("".to_string())]),
-        Type::List(vec![Type::Integer(1), Type::ByteString("rust".to_string())]),
+        Type::List(vec![Type::ByteString(b"".to_vec())]),
+        Type::List(vec![Type::Integer(1), Type::ByteString(b"rust".to_vec())]),
     ];
     let to_encode = Type::List(l);
 
@@ -201,9 +211,9 @@ fn test_encode_list_of_integers_strings_and_lists() {
 fn test_encode_list_of_lists() {
     let correct = "ll3:def1:3el0:eli1e4:rustee".bytes().collect::<Vec<u8>>();
     let l = vec![
-        Type::List(vec![Type::ByteString("def".to_string()), Type::ByteString("3".to_string())]),
-        Type::List(vec![Type::ByteString("".to_string())]),
-        Type::List(vec![Type::Integer(1), Type::ByteString("rust".to_string())]),
+        Type::List(vec![Type::ByteString(b"def".to_vec()), Type::ByteString(b"3".to_vec())]),
+        Type::List(vec![Type::ByteString(b"".to_vec())]),
+        Type::List(vec![Type::Integer(1), Type::ByteString(b"rust".to_vec())]),
     ];
     let to_encode = Type::List(l);
 
@@ -214,15 +224,15 @@ fn test_encode_list_of_lists() {
 fn test_encode_list_of_mixed_types() {
     let correct = "l3:abcl3:def1:3ei2023el0:eli1e4:rusted1:3i1e3:key3:valee".bytes().collect::<Vec<u8>>();
     let l = vec![
-        Type::ByteString("abc".to_string()),
-        Type::List(vec![Type::ByteString("def".to_string()), Type::ByteString("3".to_string())]),
+        Type::ByteString(b"abc".to_vec()),
+        Type::List(vec![Type::ByteString(b"def".to_vec()), Type::ByteString(b"3".to_vec())]),
         Type::Integer(2023),
-        Type::List(vec![Type::ByteString("".to_string())]),
-        Type::List(vec![Type::Integer(1), Type::ByteString("rust".to_string())]),
+        Type::List(vec![Type::ByteString(b"".to_vec())]),
+        Type::List(vec![Type::Integer(1), Type::ByteString(b"rust".to_vec())]),
         Type::Dictionary(BTreeMap::from(
             [
-                ("3".to_string(), Type::Integer(1)),
-                ("key".to_string(), Type::ByteString("val".to_string()))
+                (b"3".to_vec(), Type::Integer(1)),
+                (b"key".to_vec(), Type::ByteString(b"val".to_vec()))
             ]
     ))
     ];
@@ -244,9 +254,9 @@ fn test_encode_dictionary_empty() {
 fn test_encode_dictionary_of_integers() {
     let correct = "d2:k1i5e2:k2i10e2:k3i1ee".bytes().collect::<Vec<u8>>();
     let d = BTreeMap::from([
-        ("k3".to_string(), Type::Integer(1)),
-        ("k1".to_string(), Type::Integer(5)),
-        ("k2".to_string(), Type::Integer(10)),
+        (b"k3".to_vec(), Type::Integer(1)),
+        (b"k1".to_vec(), Type::Integer(5)),
+        (b"k2".to_vec(), Type::Integer(10)),
     ]);
     let to_encode = Type::Dictionary(d);
 
@@ -257,10 +267,10 @@ fn test_encode_dictionary_of_integers() {
 fn test_encode_dictionary_of_strings() {
     let correct = "d2:k12:v32:k22:v22:k32:v42:k42:v1e".bytes().collect::<Vec<u8>>();
     let d = BTreeMap::from([
-        ("k4".to_string(), Type::ByteString("v1".to_string())),
-        ("k2".to_string(), Type::ByteString("v2".to_string())),
-        ("k1".to_string(), Type::ByteString("v3".to_string())),
-        ("k3".to_string(), Type::ByteString("v4".to_string())),
+        (b"k4".to_vec(), Type::ByteString(b"v1".to_vec())),
+        (b"k2".to_vec(), Type::ByteString(b"v2".to_vec())),
+        (b"k1".to_vec(), Type::ByteString(b"v3".to_vec())),
+        (b"k3".to_vec(), Type::ByteString(b"v4".to_vec())),
     ]);
     let to_encode = Type::Dictionary(d);
 
@@ -271,10 +281,10 @@ fn test_encode_dictionary_of_strings() {
 fn test_encode_dictionary_of_integers_and_strings() {
     let correct = "d2:k1i5e2:k22:v22:k32:v42:k4i-10ee".bytes().collect::<Vec<u8>>();
     let d = BTreeMap::from([
-        ("k4".to_string(), Type::Integer(-10)),
-        ("k2".to_string(), Type::ByteString("v2".to_string())),
-        ("k1".to_string(), Type::Integer(5)),
-        ("k3".to_string(), Type::ByteString("v4".to_string())),
+        (b"k4".to_vec(), Type::Integer(-10)),
+        (b"k2".to_vec(), Type::ByteString(b"v2".to_vec())),
+        (b"k1".to_vec(), Type::Integer(5)),
+        (b"k3".to_vec(), Type::ByteString(b"v4".to_vec())),
     ]);
     let to_encode = Type::Dictionary(d);
 
@@ -285,13 +295,13 @@ fn test_encode_dictionary_of_integers_and_strings() {
 fn test_encode_dictionary_of_multiple_integers_and_strings() {
     let correct = "d1:a4:aval1:b4:bval1:ci100e2:k1i5e2:k22:v22:k32:v42:k4i-10ee".bytes().collect::<Vec<u8>>();
     let d = BTreeMap::from([
-        ("k4".to_string(), Type::Integer(-10)),
-        ("k2".to_string(), Type::ByteString("v2".to_string())),
-        ("k1".to_string(), Type::Integer(5)),
-        ("k3".to_string(), Type::ByteString("v4".to_string())),
-        ("a".to_string(), Type::ByteString("aval".to_string())),
-        ("b".to_string(), Type::ByteString("bval".to_string())),
-        ("c".to_string(), Type::Integer(100)),
+        (b"k4".to_vec(), Type::Integer(-10)),
+        (b"k2".to_vec(), Type::ByteString(b"v2".to_vec())),
+        (b"k1".to_vec(), Type::Integer(5)),
+        (b"k3".to_vec(), Type::ByteString(b"v4".to_vec())),
+        (b"a".to_vec(), Type::ByteString(b"aval".to_vec())),
+        (b"b".to_vec(), Type::ByteString(b"bval".to_vec())),
+        (b"c".to_vec(), Type::Integer(100)),
     ]);
     let to_encode = Type::Dictionary(d);
 
@@ -302,8 +312,8 @@ fn test_encode_dictionary_of_multiple_integers_and_strings() {
 fn test_encode_dictionary_of_lists_of_integers() {
     let correct = "d2:k2li2ei10ei-1ee2:k4li1ei2eee".bytes().collect::<Vec<u8>>();
     let d = BTreeMap::from([
-        ("k4".to_string(), Type::List(vec![Type::Integer(1), Type::Integer(2)])),
-        ("k2".to_string(), Type::List(vec![Type::Integer(2), Type::Integer(10), Type::Integer(-1)])),
+        (b"k4".to_vec(), Type::List(vec![Type::Integer(1), Type::Integer(2)])),
+        (b"k2".to_vec(), Type::List(vec![Type::Integer(2), Type::Integer(10), Type::Integer(-1)])),
     ]);
     let to_encode = Type::Dictionary(d);
 
@@ -314,9 +324,9 @@ fn test_encode_dictionary_of_lists_of_integers() {
 fn test_encode_dictionary_of_lists_of_strings() {
     let correct = "d2:k1le2:k3l3:abce2:k5l3:def3:ghiee".bytes().collect::<Vec<u8>>();
     let d = BTreeMap::from([
-        ("k3".to_string(), Type::List(vec![Type::ByteString("abc".to_string())])),
-        ("k1".to_string(), Type::List(vec![])),
-        ("k5".to_string(), Type::List(vec![Type::ByteString("def".to_string()), Type::ByteString("ghi".to_string())])),
+        (b"k3".to_vec(), Type::List(vec![Type::ByteString(b"abc".to_vec())])),
+        (b"k1".to_vec(), Type::List(vec![])),
+        (b"k5".to_vec(), Type::List(vec![Type::ByteString(b"def".to_vec()), Type::ByteString(b"ghi".to_vec())])),
     ]);
     let to_encode = Type::Dictionary(d);
 
@@ -327,9 +337,9 @@ fn test_encode_dictionary_of_lists_of_strings() {
 fn test_encode_dictionary_of_lists_of_integers_and_strings() {
     let correct = "d2:k1li-11e2:oke2:k3l3:abce2:k5l3:def3:ghiee".bytes().collect::<Vec<u8>>();
     let d = BTreeMap::from([
-        ("k3".to_string(), Type::List(vec![Type::ByteString("abc".to_string())])),
-        ("k1".to_string(), Type::List(vec![Type::Integer(-11), Type::ByteString("ok".to_string())])),
-        ("k5".to_string(), Type::List(vec![Type::ByteString("def".to_string()), Type::ByteString("ghi".to_string())])),
+        (b"k3".to_vec(), Type::List(vec![Type::ByteString(b"abc".to_vec())])),
+        (b"k1".to_vec(), Type::List(vec![Type::Integer(-11), Type::ByteString(b"ok".to_vec())])),
+        (b"k5".to_vec(), Type::List(vec![Type::ByteString(b"def".to_vec()), Type::ByteString(b"ghi".to_vec())])),
     ]);
     let to_encode = Type::Dictionary(d);
 
@@ -340,10 +350,10 @@ fn test_encode_dictionary_of_lists_of_integers_and_strings() {
 fn test_encode_dictionary_of_lists_of_integers_and_strings_and_dictionaries() {
     let correct = "d1:dd1:ki1ee2:k1li-11e2:oke2:k3l3:abce2:k5l3:def3:ghiee".bytes().collect::<Vec<u8>>();
     let d = BTreeMap::from([
-        ("k3".to_string(), Type::List(vec![Type::ByteString("abc".to_string())])),
-        ("k1".to_string(), Type::List(vec![Type::Integer(-11), Type::ByteString("ok".to_string())])),
-        ("k5".to_string(), Type::List(vec![Type::ByteString("def".to_string()), Type::ByteString("ghi".to_string())])),
-        ("d".to_string(), Type::Dictionary(BTreeMap::from([("k".to_string(), Type::Integer(1))]))),
+        (b"k3".to_vec(), Type::List(vec![Type::ByteString(b"abc".to_vec())])),
+        (b"k1".to_vec(), Type::List(vec![Type::Integer(-11), Type::ByteString(b"ok".to_vec())])),
+        (b"k5".to_vec(), Type::List(vec![Type::ByteString(b"def".to_vec()), Type::ByteString(b"ghi".to_vec())])),
+        (b"d".to_vec(), Type::Dictionary(BTreeMap::from([(b"k".to_vec(), Type::Integer(1))]))),
     ]);
     let to_encode = Type::Dictionary(d);
 
@@ -354,11 +364,109 @@ fn test_encode_dictionary_of_lists_of_integers_and_strings_and_dictionaries() {
 fn test_encode_dictionary_of_dictionaries() {
     let correct = "d1:dd2:k1i1ee1:fd2:k22:ffee".bytes().collect::<Vec<u8>>();
     let d = BTreeMap::from([
-        ("f".to_string(), Type::Dictionary(BTreeMap::from([("k2".to_string(), Type::ByteString("ff".to_string()))]))),
-        ("d".to_string(), Type::Dictionary(BTreeMap::from([("k1".to_string(), Type::Integer(1))]))),
+        (b"f".to_vec(), Type::Dictionary(BTreeMap::from([(b"k2".to_vec(), Type::ByteString(b"ff".to_vec()))]))),
+        (b"d".to_vec(), Type::Dictionary(BTreeMap::from([(b"k1".to_vec(), Type::Integer(1))]))),
     ]);
     let to_encode = Type::Dictionary(d);
 
     assert_eq!(encode(&to_encode), correct);
 }
 
+
+#[test]
+fn test_encode_dictionary_raw_byte_key_order() {
+    let mut correct = "d1:Bi1e1:ai2e2:".bytes().collect::<Vec<u8>>();
+    correct.extend([0xc3, 0xa9]);
+    correct.extend("i3e1:".bytes());
+    correct.push(0xff);
+    correct.extend("i4ee".bytes());
+    let d = BTreeMap::from([
+        (vec![0xff], Type::Integer(4)),
+        ("\u{e9}".as_bytes().to_vec(), Type::Integer(3)),
+        (b"a".to_vec(), Type::Integer(2)),
+        (b"B".to_vec(), Type::Integer(1)),
+    ]);
+    let to_encode = Type::Dictionary(d);
+
+    assert_eq!(encode(&to_encode), correct);
+}
+
+
+// Accepts `limit` bytes and then fails every write.
+struct FullWriter {
+    written: Vec<u8>,
+    limit: usize,
+}
+
+impl Write for FullWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = buf.len().min(self.limit - self.written.len());
+        if n == 0 && !buf.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::StorageFull, "disk full"));
+        }
+        self.written.extend_from_slice(&buf[..n]);
+
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn mixed() -> Type {
+    Type::Dictionary(BTreeMap::from([
+        (b"a".to_vec(), Type::List(vec![Type::Integer(i64::MIN), Type::Integer(i64::MAX), Type::Integer(0), Type::Integer(-9)])),
+        (b"b".to_vec(), Type::ByteString(vec![7; 1000])),
+        (vec![0xff; 12], Type::Dictionary(BTreeMap::from([(vec![], Type::List(vec![]))]))),
+    ]))
+}
+
+#[test]
+fn test_encoded_len() {
+    let values = [
+        Type::Integer(0),
+        Type::Integer(9),
+        Type::Integer(10),
+        Type::Integer(-10),
+        Type::Integer(i64::MIN),
+        Type::Integer(i64::MAX),
+        Type::ByteString(vec![]),
+        Type::ByteString(vec![0; 9]),
+        Type::ByteString(vec![0; 10]),
+        Type::List(vec![]),
+        mixed(),
+    ];
+
+    for t in values {
+        assert_eq!(encoded_len(&t), encode(&t).len(), "{:?}", t);
+    }
+}
+
+#[test]
+fn test_encode_into_matches_encode() {
+    let t = mixed();
+    let mut bytes = vec![b'x'];
+
+    encode_into(&t, &mut bytes).unwrap();
+    assert_eq!(bytes[1..], encode(&t)[..]);
+}
+
+#[test]
+fn test_encoder_encode_into() {
+    let t = mixed();
+    let mut bytes = vec![];
+
+    Encoder::encode_into(&t, &mut bytes).unwrap();
+    assert_eq!(bytes, encode(&t));
+    assert_eq!(Encoder::encoded_len(&t), bytes.len());
+}
+
+#[test]
+fn test_encode_into_write_error() {
+    let mut writer = FullWriter { written: vec![], limit: 100 };
+
+    let res = Encoder::encode_into(&mixed(), &mut writer);
+    assert_eq!(res.is_err(), true);
+    assert_eq!(writer.written, encode(&mixed())[..100]);
+}
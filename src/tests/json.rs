@@ -0,0 +1,219 @@
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::io::{self, Read, Write};
+
+use serde::Serialize;
+use serde_json::json;
+
+use crate::errors::{DeserializationError, SerializationError};
+use crate::options::{DecodeOptions, JsonOptions};
+use crate::protocol::{decode_slice, encode};
+use crate::Type;
+
+
+fn dictionary(entries: Vec<(&[u8], Type)>) -> Type {
+    Type::Dictionary(entries.into_iter().map(|(k, v)| (k.to_vec(), v)).collect())
+}
+
+fn bytes(s: &[u8]) -> Type {
+    Type::ByteString(s.to_vec())
+}
+
+#[test]
+fn test_to_json_plain_values() {
+    let t = dictionary(vec![
+        (b"announce", bytes(b"http://tracker")),
+        (b"info", dictionary(vec![(b"length", Type::Integer(5)), (b"name", bytes("café".as_bytes()))])),
+        (b"list", Type::List(vec![Type::Integer(-1), bytes(b"")])),
+    ]);
+
+    let json = t.to_json().unwrap();
+    assert_eq!(json, r#"{"announce":"http://tracker","info":{"length":5,"name":"café"},"list":[-1,""]}"#);
+    assert_eq!(Type::from_json(&json).unwrap(), t);
+}
+
+#[test]
+fn test_to_json_binary_strings() {
+    let t = dictionary(vec![(b"pieces", bytes(&[0x00, 0x9f, 0xff])), (b"name", bytes(b"\xe9t\xe9"))]);
+
+    let json = t.to_json().unwrap();
+    assert_eq!(json, r#"{"name":{"$hex":"e974e9"},"pieces":{"$hex":"009fff"}}"#);
+    assert_eq!(Type::from_json(&json).unwrap(), t);
+}
+
+#[test]
+fn test_to_json_tagged_dictionaries() {
+    let t = dictionary(vec![(b"\xff", Type::Integer(1)), (b"a", Type::Integer(2))]);
+    let json = t.to_json().unwrap();
+    assert_eq!(json, r#"{"$dict":[["a",2],[{"$hex":"ff"},1]]}"#);
+    assert_eq!(Type::from_json(&json).unwrap(), t);
+
+    // Dictionaries that would read back as a tag are written in tagged form.
+    for key in [&b"$hex"[..], b"$dict"] {
+        let t = dictionary(vec![(key, bytes(b"00"))]);
+        assert_eq!(Type::from_json(&t.to_json().unwrap()).unwrap(), t);
+    }
+
+    let t = dictionary(vec![(b"$hex", bytes(b"00")), (b"a", Type::Integer(1))]);
+    assert_eq!(t.to_json().unwrap(), r#"{"$hex":"00","a":1}"#);
+    assert_eq!(Type::from_json(&t.to_json().unwrap()).unwrap(), t);
+}
+
+#[test]
+fn test_json_round_trips_any_bencode() {
+    let all_bytes = (0..=255u8).collect::<Vec<u8>>();
+    let t = dictionary(vec![
+        (&all_bytes, Type::List(vec![bytes(&all_bytes), Type::Integer(i64::MIN), Type::Integer(i64::MAX)])),
+        (b"info", dictionary(vec![(b"$dict", Type::List(vec![])), (b"pieces", bytes(&all_bytes[128..]))])),
+    ]);
+    let encoded = encode(&t);
+
+    let json = decode_slice(&encoded, &DecodeOptions::strict()).unwrap().to_json().unwrap();
+    assert_eq!(encode(&Type::from_json(&json).unwrap()), encoded);
+}
+
+#[test]
+fn test_from_json_rejects_unsupported_values() {
+    let err = Type::from_json(r#"{"info":{"files":[{"private":true}]}}"#).unwrap_err();
+    assert_eq!(err.to_string(), "unsupported JSON value true in info.files[0].private");
+
+    let err = Type::from_json("[1.5]").unwrap_err();
+    assert_eq!(err.to_string(), "unsupported JSON value 1.5 in [0]");
+
+    let err = Type::from_json("null").unwrap_err();
+    assert_eq!(err.to_string(), "unsupported JSON value null");
+
+    let err = Type::from_json("18446744073709551615").unwrap_err();
+    assert_eq!(err.to_string(), "unsupported JSON value 18446744073709551615");
+
+    assert_eq!(matches!(Type::from_json("[1,"), Err(DeserializationError::JsonDeserializationError)), true);
+}
+
+#[test]
+fn test_from_json_policies() {
+    let json = r#"[true, false, null, 1.5, -2.5, 18446744073709551615]"#;
+
+    let t = Type::from_json_with(json, &JsonOptions::stringify()).unwrap();
+    let expected = ["true", "false", "null", "1.5", "-2.5", "18446744073709551615"];
+    assert_eq!(t, Type::List(expected.iter().map(|s| bytes(s.as_bytes())).collect()));
+
+    let json = r#"[true, false, null, 1.5, -2.5]"#;
+    let t = Type::from_json_with(json, &JsonOptions::integer()).unwrap();
+    assert_eq!(t, Type::List([1, 0, 0, 1, -2].into_iter().map(Type::Integer).collect()));
+
+    let err = Type::from_json_with("[1e300]", &JsonOptions::integer()).unwrap_err();
+    assert_eq!(err.to_string(), "unsupported JSON value 1e+300 in [0]");
+
+    // -2^63 fits in an `i64` while 2^63 does not.
+    let t = Type::from_json_with("[-9223372036854775808.0]", &JsonOptions::integer()).unwrap();
+    assert_eq!(t, Type::List(vec![Type::Integer(i64::MIN)]));
+    let err = Type::from_json_with("[9223372036854775808.0]", &JsonOptions::integer()).unwrap_err();
+    assert_eq!(err.to_string(), "unsupported JSON value 9.223372036854776e+18 in [0]");
+    let err = Type::from_json_with("18446744073709551615", &JsonOptions::integer()).unwrap_err();
+    assert_eq!(err.to_string(), "unsupported JSON value 18446744073709551615");
+}
+
+#[test]
+fn test_from_json_malformed_tags() {
+    let inputs = [
+        (r#"{"a":{"$hex":"0"}}"#, "malformed $hex object in a"),
+        (r#"{"$hex":"zz"}"#, "malformed $hex object"),
+        (r#"{"$hex":"+f"}"#, "malformed $hex object"),
+        (r#"{"$hex":1}"#, "malformed $hex object"),
+        (r#"{"$dict":{}}"#, "malformed $dict object"),
+        (r#"{"$dict":[["a"]]}"#, "malformed $dict object"),
+        (r#"{"$dict":[[1,2]]}"#, "malformed $dict object"),
+    ];
+
+    for (input, message) in inputs {
+        assert_eq!(Type::from_json(input).unwrap_err().to_string(), message);
+    }
+
+    let t = Type::from_json(r#"{"$dict":[["a",{"b":[null]}]]}"#).unwrap_err();
+    assert_eq!(t.to_string(), "unsupported JSON value null in a.b[0]");
+    assert_eq!(Type::from_json(r#"{"$dict":[]}"#).unwrap(), Type::Dictionary(BTreeMap::new()));
+}
+
+#[test]
+fn test_json_value_conversions() {
+    let t = dictionary(vec![(b"name", bytes(b"a")), (b"pieces", bytes(&[0xff])), (b"files", Type::List(vec![Type::Integer(3)]))]);
+
+    let value = serde_json::Value::from(&t);
+    assert_eq!(value, json!({"files": [3], "name": "a", "pieces": {"$hex": "ff"}}));
+    assert_eq!(serde_json::Value::from(t.clone()), value);
+    assert_eq!(Type::try_from(value).unwrap(), t);
+
+    // Embedding in a larger document needs no detour through a string.
+    let response = json!({"status": "ok", "torrent": serde_json::Value::from(&t)});
+    assert_eq!(Type::try_from(response["torrent"].clone()).unwrap(), t);
+
+    let err = Type::try_from(json!({"private": false})).unwrap_err();
+    assert_eq!(err.to_string(), "unsupported JSON value false in private");
+    let t = Type::from_json_value_with(json!({"private": false}), &JsonOptions::integer()).unwrap();
+    assert_eq!(t, dictionary(vec![(b"private", Type::Integer(0))]));
+}
+
+#[derive(Serialize)]
+struct Response {
+    status: &'static str,
+    #[serde(serialize_with = "crate::json::tagged")]
+    torrent: Type,
+}
+
+#[test]
+fn test_tagged_serialize_with() {
+    let t = dictionary(vec![(b"name", bytes(b"a")), (b"pieces", bytes(&[0x00, 0xff]))]);
+    let response = Response { status: "ok", torrent: t.clone() };
+
+    let value = serde_json::to_value(&response).unwrap();
+    assert_eq!(value["torrent"], serde_json::Value::from(&t));
+    assert_eq!(Type::try_from(value["torrent"].clone()).unwrap(), t);
+
+    // `Type`'s own `Serialize` impl is lossy for binary strings.
+    assert_ne!(serde_json::to_value(&t).unwrap(), serde_json::Value::from(&t));
+}
+
+#[test]
+fn test_read_and_write_json() {
+    let t = dictionary(vec![(b"name", bytes(b"a")), (b"pieces", bytes(&[0x00, 0xff]))]);
+
+    let mut out = vec![];
+    t.write_json(&mut out).unwrap();
+    assert_eq!(out, t.to_json().unwrap().into_bytes());
+    assert_eq!(Type::read_json(&out[..]).unwrap(), t);
+
+    let t = Type::read_json_with(&b"[null]"[..], &JsonOptions::stringify()).unwrap();
+    assert_eq!(t, Type::List(vec![bytes(b"null")]));
+    assert_eq!(matches!(Type::read_json(&b"[1"[..]), Err(DeserializationError::JsonDeserializationError)), true);
+}
+
+struct Failing;
+
+impl Read for Failing {
+    fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+        Err(io::Error::new(io::ErrorKind::BrokenPipe, "broken"))
+    }
+}
+
+impl Write for Failing {
+    fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+        Err(io::Error::new(io::ErrorKind::BrokenPipe, "broken"))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_json_io_errors() {
+    match Type::read_json(Failing) {
+        Err(DeserializationError::Io(err)) => assert_eq!(err.kind(), io::ErrorKind::BrokenPipe),
+        res => panic!("unexpected result: {:?}", res),
+    }
+
+    match Type::Integer(1).write_json(Failing) {
+        Err(SerializationError::Io(err)) => assert_eq!(err.kind(), io::ErrorKind::BrokenPipe),
+        res => panic!("unexpected result: {:?}", res),
+    }
+}
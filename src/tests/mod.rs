@@ -1,2 +1,13 @@
+#![allow(clippy::bool_assert_comparison)]
+
 mod encoder;
+mod de;
 mod decoder;
+mod json;
+mod output;
+mod raw;
+mod ser;
+mod value;
+mod writer;
+#[cfg(feature = "tokio")]
+mod async_io;
@@ -0,0 +1,96 @@
+use std::fs;
+use std::path::Path;
+
+use crate::options::WriteOptions;
+use crate::protocol::encode;
+use crate::{Decoder, Encoder, Type};
+
+
+fn torrent() -> Type {
+    Type::Dictionary([(b"announce".to_vec(), Type::ByteString(b"http://tracker".to_vec()))].into_iter().collect())
+}
+
+fn entries(dir: &Path) -> Vec<String> {
+    let mut names = fs::read_dir(dir).unwrap().map(|e| e.unwrap().file_name().to_string_lossy().into_owned()).collect::<Vec<_>>();
+    names.sort();
+    names
+}
+
+#[test]
+fn test_encode_to_create_new() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("file.torrent");
+
+    Encoder::encode_to(&torrent(), &path).unwrap();
+    assert_eq!(fs::read(&path).unwrap(), encode(&torrent()));
+
+    let res = Encoder::encode_to(&Type::Integer(1), &path);
+    assert_eq!(res.is_err(), true);
+    assert_eq!(fs::read(&path).unwrap(), encode(&torrent()));
+}
+
+#[test]
+fn test_encode_to_overwrite() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("file.torrent");
+    fs::write(&path, vec![b'x'; 1000]).unwrap();
+
+    Encoder::encode_to_with(&torrent(), &path, &WriteOptions::overwrite()).unwrap();
+    assert_eq!(fs::read(&path).unwrap(), encode(&torrent()));
+}
+
+#[test]
+fn test_encode_to_atomic() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("file.torrent");
+    fs::write(&path, b"old").unwrap();
+
+    Encoder::encode_to_with(&torrent(), &path, &WriteOptions::atomic()).unwrap();
+    assert_eq!(Decoder::decode_from(&path).ok(), Some(torrent()));
+    assert_eq!(entries(dir.path()), vec!["file.torrent"]);
+
+    let new = dir.path().join("new.torrent");
+    Encoder::encode_to_with(&Type::Integer(7), &new, &WriteOptions::atomic()).unwrap();
+    assert_eq!(fs::read(&new).unwrap(), b"i7e");
+}
+
+#[test]
+fn test_encode_to_atomic_failure_keeps_target() {
+    let dir = tempfile::tempdir().unwrap();
+    // Renaming a file over a non-empty directory fails after the temporary
+    // file has been written.
+    let path = dir.path().join("target");
+    fs::create_dir(&path).unwrap();
+    fs::write(path.join("inside"), b"old").unwrap();
+
+    let res = Encoder::encode_to_with(&torrent(), &path, &WriteOptions::atomic());
+    assert_eq!(res.is_err(), true);
+    assert_eq!(entries(dir.path()), vec!["target"]);
+    assert_eq!(fs::read(path.join("inside")).unwrap(), b"old");
+}
+
+#[test]
+fn test_encode_to_missing_directory() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("missing").join("file.torrent");
+
+    for options in [WriteOptions::create_new(), WriteOptions::overwrite(), WriteOptions::atomic()] {
+        assert_eq!(Encoder::encode_to_with(&torrent(), &path, &options).is_err(), true);
+    }
+}
+
+#[test]
+fn test_save_to_json_modes() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("file.json");
+
+    torrent().save_to_json(&path).unwrap();
+    assert_eq!(torrent().save_to_json(&path).is_err(), true);
+
+    Type::Integer(1).save_to_json_with(&path, &WriteOptions::overwrite()).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "1\n");
+
+    torrent().save_to_json_with(&path, &WriteOptions::atomic()).unwrap();
+    assert_eq!(Type::load_from_json(&path).ok(), Some(torrent()));
+    assert_eq!(entries(dir.path()), vec!["file.json"]);
+}
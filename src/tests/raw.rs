@@ -0,0 +1,83 @@
+use std::convert::TryFrom;
+
+use serde::{Deserialize, Serialize};
+
+use crate::errors::DecodeErrorKind;
+use crate::{from_bytes, from_reader, from_value, to_bytes, to_value, RawValue, Type};
+
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Torrent {
+    announce: String,
+    info: RawValue,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Info {
+    name: String,
+    length: u32,
+}
+
+// The info dictionary has its keys out of order, which a re-encoding would
+// fix, changing the infohash.
+const TORRENT: &[u8] = b"d8:announce14:http://tracker4:infod4:name1:a6:lengthi5eee";
+const INFO: &[u8] = b"d4:name1:a6:lengthi5ee";
+
+#[test]
+fn test_raw_value_keeps_bytes() {
+    let torrent: Torrent = from_bytes(TORRENT).unwrap();
+    assert_eq!(torrent.announce, "http://tracker");
+    assert_eq!(torrent.info.as_bytes(), INFO);
+
+    let torrent: Torrent = from_reader(TORRENT).unwrap();
+    assert_eq!(torrent.info.as_bytes(), INFO);
+}
+
+#[test]
+fn test_raw_value_written_verbatim() {
+    let torrent: Torrent = from_bytes(TORRENT).unwrap();
+    assert_eq!(to_bytes(&torrent).unwrap(), TORRENT);
+
+    let values: Vec<RawValue> = from_bytes(b"li1e4:spamlee").unwrap();
+    assert_eq!(values.len(), 3);
+    assert_eq!(values[1].as_bytes(), b"4:spam");
+    assert_eq!(to_bytes(&values).unwrap(), b"li1e4:spamlee");
+}
+
+#[test]
+fn test_raw_value_decode() {
+    let torrent: Torrent = from_bytes(TORRENT).unwrap();
+    let info: Info = torrent.info.decode().unwrap();
+    assert_eq!(info, Info { name: "a".to_string(), length: 5 });
+
+    let t = Type::try_from(&torrent.info).unwrap();
+    assert_eq!(RawValue::from(&t).as_bytes(), b"d6:lengthi5e4:name1:ae");
+}
+
+#[test]
+fn test_raw_value_from_bytes() {
+    let raw = RawValue::from_bytes(b"i42e".to_vec()).unwrap();
+    assert_eq!(raw.into_bytes(), b"i42e");
+
+    let err = RawValue::from_bytes(b"i42".to_vec()).unwrap_err();
+    assert_eq!(err.kind, DecodeErrorKind::InvalidInteger);
+    let err = RawValue::from_bytes(b"i1ei2e".to_vec()).unwrap_err();
+    assert_eq!(err.offset, 3);
+}
+
+#[test]
+fn test_raw_value_invalid_input() {
+    let err = from_bytes::<Torrent>(b"d8:announce1:a4:infod4:name1:a6:lengthi5e").unwrap_err();
+    assert_eq!(err.kind, DecodeErrorKind::InvalidDictionary);
+    assert_eq!(err.offset, 41);
+}
+
+#[test]
+fn test_raw_value_through_type() {
+    let torrent: Torrent = from_bytes(TORRENT).unwrap();
+    let t = to_value(&torrent).unwrap();
+    assert_eq!(t.get("info").unwrap().get("length").unwrap(), &Type::Integer(5));
+
+    let torrent: Torrent = from_value(t).unwrap();
+    assert_eq!(torrent.info.as_bytes(), b"d6:lengthi5e4:name1:ae");
+}
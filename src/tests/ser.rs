@@ -0,0 +1,192 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::Serialize;
+
+use crate::errors::SerError;
+use crate::protocol::encode;
+use crate::{to_bytes, to_writer, Type};
+
+
+#[derive(Serialize)]
+struct Info {
+    name: String,
+    #[serde(rename = "piece length")]
+    piece_length: u32,
+    #[serde(with = "serde_bytes")]
+    pieces: Vec<u8>,
+    length: Option<u64>,
+    private: bool,
+}
+
+#[derive(Serialize)]
+struct Torrent {
+    info: Info,
+    announce: String,
+    #[serde(rename = "announce-list")]
+    announce_list: Vec<Vec<String>>,
+    comment: Option<String>,
+}
+
+#[derive(Serialize)]
+enum Message {
+    Ping,
+    Have(u32),
+    Request(u32, u32, u32),
+    Cancel { index: u32, begin: u32 },
+}
+
+fn info() -> Info {
+    Info { name: "file.bin".to_string(), piece_length: 16384, pieces: vec![0, 255, 1], length: Some(5), private: true }
+}
+
+#[test]
+fn test_to_bytes_struct_sorted_keys() {
+    let bytes = to_bytes(&info()).unwrap();
+
+    assert_eq!(bytes, b"d6:lengthi5e4:name8:file.bin12:piece lengthi16384e6:pieces3:\x00\xff\x017:privatei1ee");
+}
+
+#[test]
+fn test_to_bytes_nested_struct() {
+    let torrent = Torrent { info: info(), announce: "http://a".to_string(), announce_list: vec![vec!["http://a".to_string()], vec![]], comment: None };
+    let bytes = to_bytes(&torrent).unwrap();
+
+    let mut expected = b"d8:announce8:http://a13:announce-listll8:http://aelee4:info".to_vec();
+    expected.extend(to_bytes(&info()).unwrap());
+    expected.push(b'e');
+    assert_eq!(bytes, expected);
+}
+
+#[test]
+fn test_to_bytes_skips_none_fields() {
+    let info = Info { length: None, ..info() };
+    let bytes = to_bytes(&info).unwrap();
+
+    assert_eq!(bytes, b"d4:name8:file.bin12:piece lengthi16384e6:pieces3:\x00\xff\x017:privatei1ee");
+}
+
+#[test]
+fn test_to_bytes_none_outside_dictionary() {
+    let res = to_bytes(&vec![Some(1), None]);
+    assert_eq!(matches!(res, Err(SerError::UnsupportedNone)), true);
+
+    let res = to_bytes(&None::<i64>);
+    assert_eq!(matches!(res, Err(SerError::UnsupportedNone)), true);
+}
+
+#[test]
+fn test_to_bytes_wrapped_none_in_dictionary() {
+    #[derive(Serialize)]
+    struct Wrapper(Option<i64>);
+
+    #[derive(Serialize)]
+    struct S {
+        a: Wrapper,
+        b: Option<Option<i64>>,
+        c: i64,
+    }
+
+    let res = to_bytes(&S { a: Wrapper(None), b: None, c: 1 });
+    assert_eq!(matches!(res, Err(SerError::UnsupportedNone)), true);
+
+    let res = to_bytes(&S { a: Wrapper(Some(2)), b: Some(None), c: 1 });
+    assert_eq!(matches!(res, Err(SerError::UnsupportedNone)), true);
+
+    let bytes = to_bytes(&S { a: Wrapper(Some(2)), b: None, c: 1 }).unwrap();
+    assert_eq!(bytes, b"d1:ai2e1:ci1ee");
+}
+
+#[test]
+fn test_to_bytes_enum_variants() {
+    assert_eq!(to_bytes(&Message::Ping).unwrap(), b"4:Ping");
+    assert_eq!(to_bytes(&Message::Have(7)).unwrap(), b"d4:Havei7ee");
+    assert_eq!(to_bytes(&Message::Request(1, 2, 3)).unwrap(), b"d7:Requestli1ei2ei3eee");
+    assert_eq!(to_bytes(&Message::Cancel { index: 1, begin: 2 }).unwrap(), b"d6:Canceld5:begini2e5:indexi1eee");
+    assert_eq!(to_bytes(&vec![Message::Ping, Message::Have(1)]).unwrap(), b"l4:Pingd4:Havei1eee");
+}
+
+#[test]
+fn test_to_bytes_maps() {
+    let map = HashMap::from([("b", 2), ("a", 1), ("c", 3)]);
+    assert_eq!(to_bytes(&map).unwrap(), b"d1:ai1e1:bi2e1:ci3ee");
+
+    let map = BTreeMap::from([(10, "x"), (2, "y")]);
+    assert_eq!(to_bytes(&map).unwrap(), b"d2:101:x1:21:ye");
+
+    let map = HashMap::from([("a", Some(1)), ("b", None)]);
+    assert_eq!(to_bytes(&map).unwrap(), b"d1:ai1ee");
+}
+
+#[test]
+fn test_to_bytes_invalid_key() {
+    let map = BTreeMap::from([(vec![1], 1)]);
+    let res = to_bytes(&map);
+
+    assert_eq!(matches!(res, Err(SerError::InvalidKey)), true);
+}
+
+#[test]
+fn test_to_bytes_duplicate_key() {
+    #[derive(Serialize)]
+    struct Inner {
+        a: i64,
+    }
+
+    #[derive(Serialize)]
+    struct Outer {
+        a: i64,
+        #[serde(flatten)]
+        inner: Inner,
+    }
+
+    let res = to_bytes(&Outer { a: 1, inner: Inner { a: 2 } });
+    assert_eq!(matches!(res, Err(SerError::DuplicateKey)), true);
+}
+
+#[test]
+fn test_to_bytes_unsupported_types() {
+    let res = to_bytes(&1.5f64);
+    assert_eq!(matches!(res, Err(SerError::UnsupportedType("f64"))), true);
+    assert_eq!(res.unwrap_err().to_string(), "f64 cannot be represented in bencode");
+
+    assert_eq!(matches!(to_bytes(&vec![1.5f32]), Err(SerError::UnsupportedType("f32"))), true);
+    assert_eq!(matches!(to_bytes(&()), Err(SerError::UnsupportedType("unit"))), true);
+}
+
+#[test]
+fn test_to_bytes_integer_range() {
+    assert_eq!(to_bytes(&(i64::MAX as u64)).unwrap(), format!("i{}e", i64::MAX).into_bytes());
+    assert_eq!(to_bytes(&-5i8).unwrap(), b"i-5e");
+    assert_eq!(to_bytes(&false).unwrap(), b"i0e");
+
+    assert_eq!(matches!(to_bytes(&u64::MAX), Err(SerError::IntegerOutOfRange)), true);
+    assert_eq!(matches!(to_bytes(&(i64::MIN as i128 - 1)), Err(SerError::IntegerOutOfRange)), true);
+}
+
+#[test]
+fn test_to_bytes_strings() {
+    assert_eq!(to_bytes("spam").unwrap(), b"4:spam");
+    assert_eq!(to_bytes(&'é').unwrap(), "2:é".as_bytes());
+    assert_eq!(to_bytes(&serde_bytes::Bytes::new(b"\x00\x01")).unwrap(), b"2:\x00\x01");
+    assert_eq!(to_bytes(&(1, "a")).unwrap(), b"li1e1:ae");
+}
+
+#[test]
+fn test_to_bytes_type_matches_encode() {
+    let t = Type::Dictionary(BTreeMap::from([
+        (b"z".to_vec(), Type::ByteString(vec![0xff, 0x00])),
+        (b"a".to_vec(), Type::List(vec![Type::Integer(-1), Type::ByteString(b"x".to_vec())])),
+        (vec![0xfe], Type::Dictionary(BTreeMap::new())),
+    ]));
+
+    assert_eq!(to_bytes(&t).unwrap(), encode(&t));
+}
+
+#[test]
+fn test_to_writer() {
+    let mut out = b"prefix".to_vec();
+    to_writer(&mut out, &info()).unwrap();
+
+    assert_eq!(&out[..6], b"prefix");
+    assert_eq!(&out[6..], &to_bytes(&info()).unwrap()[..]);
+}
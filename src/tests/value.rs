@@ -0,0 +1,144 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::errors::{ConverterError, SerError};
+use crate::protocol::decode_slice;
+use crate::options::DecodeOptions;
+use crate::{from_value, to_bytes, to_value, Type};
+
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct File {
+    length: u64,
+    path: Vec<String>,
+    md5sum: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Info {
+    name: String,
+    #[serde(with = "serde_bytes")]
+    pieces: Vec<u8>,
+    files: Vec<File>,
+    #[serde(default)]
+    private: bool,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+enum Message {
+    Ping,
+    Have(u32),
+    Request(u32, u32, u32),
+    Cancel { index: u32, begin: u32 },
+}
+
+fn info() -> Info {
+    Info {
+        name: "dir".to_string(),
+        pieces: vec![0xff, 0x00],
+        files: vec![
+            File { length: 5, path: vec!["a".to_string()], md5sum: None },
+            File { length: 7, path: vec!["b".to_string()], md5sum: Some("x".to_string()) },
+        ],
+        private: true,
+    }
+}
+
+fn bytes(s: &str) -> Type {
+    Type::ByteString(s.as_bytes().to_vec())
+}
+
+#[test]
+fn test_to_value_matches_to_bytes() {
+    let bytes = to_bytes(&info()).unwrap();
+
+    assert_eq!(to_value(&info()).unwrap(), decode_slice(&bytes, &DecodeOptions::default()).unwrap());
+}
+
+#[test]
+fn test_to_value_skips_none() {
+    let t = to_value(&info().files[0]).unwrap();
+
+    assert_eq!(t, Type::Dictionary(BTreeMap::from([
+        (b"length".to_vec(), Type::Integer(5)),
+        (b"path".to_vec(), Type::List(vec![bytes("a")])),
+    ])));
+}
+
+#[test]
+fn test_to_value_enum() {
+    assert_eq!(to_value(&Message::Ping).unwrap(), bytes("Ping"));
+    assert_eq!(to_value(&Message::Have(1)).unwrap(), Type::Dictionary(BTreeMap::from([(b"Have".to_vec(), Type::Integer(1))])));
+
+    for message in [Message::Ping, Message::Have(7), Message::Request(1, 2, 3), Message::Cancel { index: 1, begin: 2 }] {
+        assert_eq!(to_value(&message).unwrap(), decode_slice(&to_bytes(&message).unwrap(), &DecodeOptions::default()).unwrap());
+        assert_eq!(from_value::<Message>(to_value(&message).unwrap()).unwrap(), message);
+    }
+}
+
+#[test]
+fn test_to_value_errors() {
+    assert_eq!(matches!(to_value(&0.5f64), Err(SerError::UnsupportedType("f64"))), true);
+    assert_eq!(matches!(to_value(&vec![None::<i64>]), Err(SerError::UnsupportedNone)), true);
+    assert_eq!(matches!(to_value(&None::<i64>), Err(SerError::UnsupportedNone)), true);
+    assert_eq!(matches!(to_value(&BTreeMap::from([("a", Some(None::<i64>))])), Err(SerError::UnsupportedNone)), true);
+    assert_eq!(matches!(to_value(&u64::MAX), Err(SerError::IntegerOutOfRange)), true);
+}
+
+#[test]
+fn test_type_round_trip() {
+    let t = Type::Dictionary(BTreeMap::from([
+        (vec![0xfe], Type::List(vec![Type::Integer(-1), Type::ByteString(vec![0xff])])),
+        (b"a".to_vec(), Type::Dictionary(BTreeMap::new())),
+    ]));
+
+    assert_eq!(to_value(&t).unwrap(), t);
+    assert_eq!(from_value::<Type>(t.clone()).unwrap(), t);
+}
+
+#[test]
+fn test_from_value_patched() {
+    let mut t = to_value(&info()).unwrap();
+    if let Type::Dictionary(d) = &mut t {
+        d.insert(b"name".to_vec(), bytes("renamed"));
+        d.insert(b"unknown".to_vec(), Type::Integer(1));
+        d.remove(b"private".as_slice());
+    }
+
+    let info = from_value::<Info>(t).unwrap();
+    assert_eq!(info.name, "renamed");
+    assert_eq!(info.private, false);
+    assert_eq!(info.files, self::info().files);
+}
+
+#[test]
+fn test_from_value_error_paths() {
+    let mut t = to_value(&info()).unwrap();
+    if let Type::Dictionary(d) = &mut t {
+        if let Some(Type::List(files)) = d.get_mut(b"files".as_slice()) {
+            files[1] = Type::Dictionary(BTreeMap::from([(b"length".to_vec(), bytes("7")), (b"path".to_vec(), Type::List(vec![]))]));
+        }
+    }
+
+    let err = from_value::<Info>(t).unwrap_err();
+    assert_eq!(err.to_string(), "invalid type: byte array, expected u64 in files[1].length");
+
+    let t = Type::Dictionary(BTreeMap::from([(b"info".to_vec(), Type::Dictionary(BTreeMap::new()))]));
+    let err = from_value::<BTreeMap<String, Info>>(t).unwrap_err();
+    assert_eq!(err.to_string(), "missing field `name` in info");
+
+    let err = from_value::<Message>(Type::Dictionary(BTreeMap::from([(b"Have".to_vec(), bytes("x"))]))).unwrap_err();
+    assert_eq!(matches!(&err, ConverterError::Custom { path, .. } if path == "Have"), true);
+}
+
+#[test]
+fn test_from_value_lengths_and_bools() {
+    let t = Type::List(vec![Type::Integer(1), Type::Integer(2), Type::Integer(3)]);
+    assert_eq!(from_value::<(i64, i64)>(t.clone()).is_err(), true);
+    assert_eq!(from_value::<(i64, i64, i64)>(t).unwrap(), (1, 2, 3));
+
+    assert_eq!(from_value::<bool>(Type::Integer(1)).unwrap(), true);
+    assert_eq!(from_value::<bool>(Type::Integer(2)).is_err(), true);
+    assert_eq!(from_value::<String>(Type::ByteString(vec![0xff])).is_err(), true);
+}
@@ -0,0 +1,138 @@
+use std::collections::BTreeMap;
+
+use crate::errors::WriterError;
+use crate::options::DecodeOptions;
+use crate::protocol::{decode_slice, encode, BencodeWriter};
+use crate::Type;
+
+
+#[test]
+fn test_writer_matches_encode() {
+    let mut w = BencodeWriter::canonical(vec![]);
+    w.begin_dict().unwrap();
+    w.key("announce").unwrap();
+    w.bytes("http://tracker").unwrap();
+    w.key("info").unwrap();
+    w.begin_dict().unwrap();
+    w.key("length").unwrap();
+    w.int(-42).unwrap();
+    w.key("list").unwrap();
+    w.begin_list().unwrap();
+    w.bytes([]).unwrap();
+    w.begin_list().unwrap();
+    w.end().unwrap();
+    w.end().unwrap();
+    w.end().unwrap();
+    w.end().unwrap();
+    let bytes = w.finish().unwrap();
+
+    let mut info = BTreeMap::new();
+    info.insert(b"length".to_vec(), Type::Integer(-42));
+    info.insert(b"list".to_vec(), Type::List(vec![Type::ByteString(vec![]), Type::List(vec![])]));
+    let mut d = BTreeMap::new();
+    d.insert(b"announce".to_vec(), Type::ByteString(b"http://tracker".to_vec()));
+    d.insert(b"info".to_vec(), Type::Dictionary(info));
+
+    assert_eq!(bytes, encode(&Type::Dictionary(d)));
+}
+
+#[test]
+fn test_writer_incremental_bytes() {
+    let pieces = (0..=255u8).collect::<Vec<u8>>();
+    let mut w = BencodeWriter::new(vec![]);
+
+    w.begin_dict().unwrap();
+    w.key("pieces").unwrap();
+    w.begin_bytes(pieces.len()).unwrap();
+    for chunk in pieces.chunks(20) {
+        w.write_bytes(chunk).unwrap();
+    }
+    w.key("x").unwrap();
+    w.value(&Type::List(vec![Type::Integer(1)])).unwrap();
+    w.end().unwrap();
+    let bytes = w.finish().unwrap();
+
+    let t = decode_slice(&bytes, &DecodeOptions::strict()).unwrap();
+    assert_eq!(t.get("pieces").ok(), Some(&Type::ByteString(pieces)));
+    assert_eq!(t.get("x").ok(), Some(&Type::List(vec![Type::Integer(1)])));
+}
+
+#[test]
+fn test_writer_nesting_errors() {
+    let mut w = BencodeWriter::new(vec![]);
+    assert!(matches!(w.end(), Err(WriterError::UnexpectedEnd)));
+    assert!(matches!(w.key("a"), Err(WriterError::UnexpectedKey)));
+
+    w.begin_list().unwrap();
+    assert!(matches!(w.key("a"), Err(WriterError::UnexpectedKey)));
+    w.begin_dict().unwrap();
+    assert!(matches!(w.int(1), Err(WriterError::MissingKey)));
+    w.key("a").unwrap();
+    assert!(matches!(w.key("b"), Err(WriterError::UnexpectedKey)));
+    assert!(matches!(w.end(), Err(WriterError::MissingValue)));
+    w.int(1).unwrap();
+    w.end().unwrap();
+    w.end().unwrap();
+
+    assert!(matches!(w.int(2), Err(WriterError::ValueAfterEnd)));
+    assert!(matches!(w.end(), Err(WriterError::UnexpectedEnd)));
+    assert_eq!(w.finish().unwrap(), b"ld1:ai1eee");
+}
+
+#[test]
+fn test_writer_byte_string_length_errors() {
+    let mut w = BencodeWriter::new(vec![]);
+    w.begin_list().unwrap();
+    w.begin_bytes(4).unwrap();
+    w.write_bytes(b"ab").unwrap();
+
+    assert!(matches!(w.write_bytes(b"cde"), Err(WriterError::ByteStringTooLong)));
+    assert!(matches!(w.end(), Err(WriterError::IncompleteByteString)));
+    assert!(matches!(w.int(1), Err(WriterError::IncompleteByteString)));
+
+    w.write_bytes(b"cd").unwrap();
+    w.end().unwrap();
+    assert_eq!(w.finish().unwrap(), b"l4:abcde");
+}
+
+#[test]
+fn test_writer_canonical_keys() {
+    let mut w = BencodeWriter::canonical(vec![]);
+    w.begin_dict().unwrap();
+    w.key("b").unwrap();
+    w.int(1).unwrap();
+
+    assert!(matches!(w.key("a"), Err(WriterError::UnsortedDictionaryKeys)));
+    assert!(matches!(w.key("b"), Err(WriterError::DuplicateDictionaryKey)));
+
+    w.key("c").unwrap();
+    w.begin_dict().unwrap();
+    w.key("a").unwrap();
+    w.int(2).unwrap();
+    w.end().unwrap();
+    w.end().unwrap();
+    assert_eq!(w.finish().unwrap(), b"d1:bi1e1:cd1:ai2eee");
+}
+
+#[test]
+fn test_writer_non_canonical_keys() {
+    let mut w = BencodeWriter::new(vec![]);
+    w.begin_dict().unwrap();
+    w.key("b").unwrap();
+    w.int(1).unwrap();
+    w.key("a").unwrap();
+    w.int(2).unwrap();
+    w.end().unwrap();
+
+    assert_eq!(w.finish().unwrap(), b"d1:bi1e1:ai2ee");
+}
+
+#[test]
+fn test_writer_incomplete() {
+    let w = BencodeWriter::new(vec![]);
+    assert!(matches!(w.finish(), Err(WriterError::Incomplete)));
+
+    let mut w = BencodeWriter::new(vec![]);
+    w.begin_list().unwrap();
+    assert!(matches!(w.finish(), Err(WriterError::Incomplete)));
+}
@@ -0,0 +1,85 @@
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+use crate::errors::ConverterError;
+use crate::types::Type;
+
+/// A decoded value whose byte strings and dictionary keys borrow from the
+/// input instead of being copied out of it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TypeRef<'a> {
+    Integer(i64),
+    ByteString(&'a [u8]),
+    List(Vec<TypeRef<'a>>),
+    Dictionary(BTreeMap<&'a [u8], TypeRef<'a>>),
+}
+
+impl<'a> TypeRef<'a> {
+    /// Copies the value into an owned `Type`.
+    pub fn to_type(&self) -> Type {
+        match self {
+            TypeRef::Integer(i) => Type::Integer(*i),
+            TypeRef::ByteString(s) => Type::ByteString(s.to_vec()),
+            TypeRef::List(l) => Type::List(l.iter().map(TypeRef::to_type).collect()),
+            TypeRef::Dictionary(d) => Type::Dictionary(d.iter().map(|(k, v)| (k.to_vec(), v.to_type())).collect()),
+        }
+    }
+
+    pub fn get<K>(&self, key: K) -> Result<&TypeRef<'a>, ConverterError>
+    where
+        K: AsRef<[u8]>,
+    {
+        match self {
+            TypeRef::Dictionary(d) => match d.get(key.as_ref()) {
+                Some(t) => Ok(t),
+                None => Err(ConverterError::InvalidDictionary),
+            },
+            _ => Err(ConverterError::InvalidDictionary),
+        }
+    }
+}
+
+impl From<&TypeRef<'_>> for Type {
+    fn from(value: &TypeRef<'_>) -> Self {
+        value.to_type()
+    }
+}
+
+impl From<TypeRef<'_>> for Type {
+    fn from(value: TypeRef<'_>) -> Self {
+        value.to_type()
+    }
+}
+
+impl<'a> TryFrom<&TypeRef<'a>> for &'a [u8] {
+    type Error = ConverterError;
+
+    fn try_from(value: &TypeRef<'a>) -> Result<Self, Self::Error> {
+        match value {
+            TypeRef::ByteString(s) => Ok(s),
+            _ => Err(ConverterError::InvalidString),
+        }
+    }
+}
+
+impl<'a> TryFrom<&TypeRef<'a>> for &'a str {
+    type Error = ConverterError;
+
+    fn try_from(value: &TypeRef<'a>) -> Result<Self, Self::Error> {
+        match value {
+            TypeRef::ByteString(s) => std::str::from_utf8(s).map_err(|_| ConverterError::InvalidString),
+            _ => Err(ConverterError::InvalidString),
+        }
+    }
+}
+
+impl TryFrom<&TypeRef<'_>> for i64 {
+    type Error = ConverterError;
+
+    fn try_from(value: &TypeRef<'_>) -> Result<Self, Self::Error> {
+        match value {
+            TypeRef::Integer(i) => Ok(*i),
+            _ => Err(ConverterError::InvalidInteger),
+        }
+    }
+}
@@ -1,27 +1,57 @@
-use serde::{Deserialize, Serialize};
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
 use std::collections::BTreeMap;
 use std::convert::TryFrom;
-use std::fs::{read_to_string, OpenOptions};
-use std::io::Write;
+use std::fmt;
+use std::fs::read_to_string;
+use std::io::{self, Read, Write};
+use std::mem;
 use std::path::Path;
 
 use crate::errors::{ConverterError, DeserializationError, SerializationError};
+use crate::json::{from_json_value, to_json_value};
+use crate::options::{JsonOptions, WriteOptions};
+use crate::output::OutputFile;
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
-#[serde(untagged)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Type {
     Integer(i64),
-    ByteString(String),
+    ByteString(Vec<u8>),
     List(Vec<Type>),
-    Dictionary(BTreeMap<String, Type>),
+    Dictionary(BTreeMap<Vec<u8>, Type>),
+}
+
+// The derived drop would recurse once per nesting level, so deeply nested
+// values could overflow the stack. Nested containers are emptied into a
+// single list instead and dropped one by one.
+impl Drop for Type {
+    fn drop(&mut self) {
+        let mut stack = match self {
+            Type::List(l) if l.iter().any(is_container) => mem::take(l),
+            Type::Dictionary(d) if d.values().any(is_container) => mem::take(d).into_values().collect(),
+            _ => return,
+        };
+
+        while let Some(mut t) = stack.pop() {
+            match &mut t {
+                Type::List(l) => stack.append(l),
+                Type::Dictionary(d) => stack.extend(mem::take(d).into_values()),
+                _ => {},
+            }
+        }
+    }
+}
+
+fn is_container(t: &Type) -> bool {
+    matches!(t, Type::List(_) | Type::Dictionary(_))
 }
 
 impl TryFrom<Type> for String {
     type Error = ConverterError;
 
-    fn try_from(value: Type) -> Result<Self, Self::Error> {
-        match value {
-            Type::ByteString(s) => Ok(s),
+    fn try_from(mut value: Type) -> Result<Self, Self::Error> {
+        match &mut value {
+            Type::ByteString(s) => String::from_utf8(mem::take(s)).map_err(|_| ConverterError::InvalidString),
             _ => Err(ConverterError::InvalidString),
         }
     }
@@ -32,7 +62,29 @@ impl TryFrom<&Type> for String {
 
     fn try_from(value: &Type) -> Result<Self, Self::Error> {
         match value {
-            Type::ByteString(s) => Ok(s.to_string()),
+            Type::ByteString(s) => String::from_utf8(s.clone()).map_err(|_| ConverterError::InvalidString),
+            _ => Err(ConverterError::InvalidString),
+        }
+    }
+}
+
+impl TryFrom<Type> for Vec<u8> {
+    type Error = ConverterError;
+
+    fn try_from(mut value: Type) -> Result<Self, Self::Error> {
+        match &mut value {
+            Type::ByteString(s) => Ok(mem::take(s)),
+            _ => Err(ConverterError::InvalidString),
+        }
+    }
+}
+
+impl TryFrom<&Type> for Vec<u8> {
+    type Error = ConverterError;
+
+    fn try_from(value: &Type) -> Result<Self, Self::Error> {
+        match value {
+            Type::ByteString(s) => Ok(s.clone()),
             _ => Err(ConverterError::InvalidString),
         }
     }
@@ -50,15 +102,62 @@ impl TryFrom<Type> for i64 {
 }
 
 impl Type {
-    pub fn from_json(s: &String) -> Result<Type, DeserializationError> {
-        match serde_json::from_str::<Type>(s) {
+    /// Reads JSON written by `to_json`, rejecting booleans, floats and
+    /// `null`, which have no bencode form.
+    pub fn from_json(s: &str) -> Result<Type, DeserializationError> {
+        Type::from_json_with(s, &JsonOptions::default())
+    }
+
+    pub fn from_json_with(s: &str, options: &JsonOptions) -> Result<Type, DeserializationError> {
+        match serde_json::from_str::<serde_json::Value>(s) {
+            Err(_) => Err(DeserializationError::JsonDeserializationError),
+            Ok(value) => from_json_value(value, options),
+        }
+    }
+
+    /// Converts JSON such as `serde_json::Value::from(&t)` gives back to a
+    /// `Type`, handling values bencode has no form for as `options` says.
+    /// `Type::try_from` does the same with the default options.
+    pub fn from_json_value_with(value: serde_json::Value, options: &JsonOptions) -> Result<Type, DeserializationError> {
+        from_json_value(value, options)
+    }
+
+    /// Reads a single JSON value from `reader`.
+    pub fn read_json<R>(reader: R) -> Result<Type, DeserializationError>
+    where
+        R: Read,
+    {
+        Type::read_json_with(reader, &JsonOptions::default())
+    }
+
+    pub fn read_json_with<R>(reader: R, options: &JsonOptions) -> Result<Type, DeserializationError>
+    where
+        R: Read,
+    {
+        match serde_json::from_reader::<R, serde_json::Value>(reader) {
+            Err(err) if err.is_io() => Err(DeserializationError::Io(io::Error::from(err))),
             Err(_) => Err(DeserializationError::JsonDeserializationError),
-            Ok(t) => Ok(t),
+            Ok(value) => from_json_value(value, options),
+        }
+    }
+
+    /// Writes the value as JSON to `writer`, in the form `to_json` gives.
+    pub fn write_json<W>(&self, writer: W) -> Result<(), SerializationError>
+    where
+        W: Write,
+    {
+        match serde_json::to_writer(writer, &to_json_value(self)) {
+            Err(err) if err.is_io() => Err(SerializationError::Io(io::Error::from(err))),
+            Err(_) => Err(SerializationError::JsonSerializationError),
+            Ok(_) => Ok(()),
         }
     }
 
+    /// Writes the value as JSON that `from_json` reads back exactly. Byte
+    /// strings that are not UTF-8 are written as `{"$hex": "..."}`, and
+    /// dictionaries with such keys as `{"$dict": [[key, value], ...]}`.
     pub fn to_json(&self) -> Result<String, SerializationError> {
-        match serde_json::to_string(self) {
+        match serde_json::to_string(&to_json_value(self)) {
             Err(_) => Err(SerializationError::JsonSerializationError),
             Ok(str) => Ok(str),
         }
@@ -69,30 +168,40 @@ impl Type {
         P: AsRef<Path>,
     {
         match read_to_string(path) {
-            Err(_) => return Err(DeserializationError::FileError),
+            Err(_) => Err(DeserializationError::FileError),
             Ok(json_str) => Type::from_json(&json_str),
         }
     }
 
     pub fn save_to_json<P>(&self, path: P) -> Result<(), SerializationError>
+    where
+        P: AsRef<Path>,
+    {
+        self.save_to_json_with(path, &WriteOptions::default())
+    }
+
+    pub fn save_to_json_with<P>(&self, path: P, options: &WriteOptions) -> Result<(), SerializationError>
     where
         P: AsRef<Path>,
     {
         let json = self.to_json()?;
-        let file = OpenOptions::new().write(true).create_new(true).open(path);
+        let file = OutputFile::create(path, options);
 
         match file {
-            Err(_) => return Err(SerializationError::FileError),
-            Ok(mut file) => match writeln!(file, "{}", json) {
+            Err(_) => Err(SerializationError::FileError),
+            Ok(mut file) => match writeln!(file, "{}", json).and_then(|_| file.commit()) {
                 Ok(_) => Ok(()),
                 Err(_) => Err(SerializationError::FileSerializationError),
             },
         }
     }
 
-    pub fn get(&self, key: String) -> Result<&Type, ConverterError> {
+    pub fn get<K>(&self, key: K) -> Result<&Type, ConverterError>
+    where
+        K: AsRef<[u8]>,
+    {
         match self {
-            Type::Dictionary(d) => match d.get(&key) {
+            Type::Dictionary(d) => match d.get(key.as_ref()) {
                 Some(t) => Ok(t),
                 None => Err(ConverterError::InvalidDictionary),
             },
@@ -100,3 +209,183 @@ impl Type {
         }
     }
 }
+
+// Byte strings are written as text for human readable formats such as JSON
+// and as raw bytes everywhere else. Non UTF-8 byte strings fall back to one
+// char per byte, which keeps them printable but does not survive a round
+// trip; `to_json` and `json::tagged` use a mapping that does.
+fn serialize_bytes<S>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    if !serializer.is_human_readable() {
+        return serializer.serialize_bytes(bytes);
+    }
+
+    match std::str::from_utf8(bytes) {
+        Ok(s) => serializer.serialize_str(s),
+        Err(_) => serializer.serialize_str(&bytes.iter().map(|&b| b as char).collect::<String>()),
+    }
+}
+
+struct BytesRef<'a>(&'a [u8]);
+
+impl Serialize for BytesRef<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serialize_bytes(self.0, serializer)
+    }
+}
+
+impl Serialize for Type {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Type::Integer(i) => serializer.serialize_i64(*i),
+            Type::ByteString(s) => serialize_bytes(s, serializer),
+            Type::List(l) => {
+                let mut seq = serializer.serialize_seq(Some(l.len()))?;
+                for t in l {
+                    seq.serialize_element(t)?;
+                }
+                seq.end()
+            },
+            Type::Dictionary(d) => {
+                let mut map = serializer.serialize_map(Some(d.len()))?;
+                for (k, v) in d {
+                    map.serialize_entry(&BytesRef(k), v)?;
+                }
+                map.end()
+            },
+        }
+    }
+}
+
+struct TypeVisitor;
+
+impl<'de> Visitor<'de> for TypeVisitor {
+    type Value = Type;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an integer, byte string, list or dictionary")
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Type, E>
+    where
+        E: de::Error,
+    {
+        Ok(Type::Integer(v))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Type, E>
+    where
+        E: de::Error,
+    {
+        match i64::try_from(v) {
+            Ok(i) => Ok(Type::Integer(i)),
+            Err(_) => Err(E::custom("integer out of range")),
+        }
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Type, E>
+    where
+        E: de::Error,
+    {
+        Ok(Type::ByteString(v.as_bytes().to_vec()))
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Type, E>
+    where
+        E: de::Error,
+    {
+        Ok(Type::ByteString(v.to_vec()))
+    }
+
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Type, E>
+    where
+        E: de::Error,
+    {
+        Ok(Type::ByteString(v))
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Type, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut l = vec![];
+
+        while let Some(t) = seq.next_element()? {
+            l.push(t);
+        }
+
+        Ok(Type::List(l))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Type, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut d = BTreeMap::new();
+
+        while let Some((ByteBuf(k), v)) = map.next_entry()? {
+            d.insert(k, v);
+        }
+
+        Ok(Type::Dictionary(d))
+    }
+}
+
+struct ByteBuf(Vec<u8>);
+
+struct ByteBufVisitor;
+
+impl<'de> Visitor<'de> for ByteBufVisitor {
+    type Value = ByteBuf;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a byte string")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<ByteBuf, E>
+    where
+        E: de::Error,
+    {
+        Ok(ByteBuf(v.as_bytes().to_vec()))
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<ByteBuf, E>
+    where
+        E: de::Error,
+    {
+        Ok(ByteBuf(v.to_vec()))
+    }
+
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<ByteBuf, E>
+    where
+        E: de::Error,
+    {
+        Ok(ByteBuf(v))
+    }
+}
+
+impl<'de> Deserialize<'de> for ByteBuf {
+    fn deserialize<D>(deserializer: D) -> Result<ByteBuf, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_bytes(ByteBufVisitor)
+    }
+}
+
+impl<'de> Deserialize<'de> for Type {
+    fn deserialize<D>(deserializer: D) -> Result<Type, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(TypeVisitor)
+    }
+}
@@ -0,0 +1,674 @@
+use std::collections::BTreeMap;
+use std::mem;
+
+use serde::de::{self, DeserializeOwned, DeserializeSeed, Unexpected, Visitor};
+use serde::forward_to_deserialize_any;
+use serde::ser::{self, Serialize};
+
+use crate::errors::{ConverterError, SerError};
+use crate::options::DecodeOptions;
+use crate::protocol::{decode_slice, encode, DeBytes, KeyDeserializer, UnitVariant};
+use crate::raw;
+use crate::ser::KeySerializer;
+use crate::types::Type;
+
+
+/// Converts `value` to a `Type`, following the same rules as `to_bytes`.
+pub fn to_value<T>(value: &T) -> Result<Type, SerError>
+where
+    T: Serialize + ?Sized
+{
+    match value.serialize(ValueSerializer { skip_none: false, raw: false })? {
+        Some(t) => Ok(t),
+        None => Err(SerError::UnsupportedNone),
+    }
+}
+
+/// Converts a `Type` to a `T`, reading it the way `from_bytes` reads its
+/// encoding. Errors name the path of the offending value.
+pub fn from_value<T>(t: Type) -> Result<T, ConverterError>
+where
+    T: DeserializeOwned
+{
+    T::deserialize(ValueDeserializer { value: t })
+}
+
+// Builds a `Type`, or `None` for a `None` that is to be left out of its
+// dictionary.
+struct ValueSerializer {
+    skip_none: bool,
+    // Set for the bytes of a `RawValue`, which are decoded.
+    raw: bool,
+}
+
+impl ValueSerializer {
+    fn integer<I>(i: I) -> Result<Option<Type>, SerError>
+    where
+        I: TryInto<i64>
+    {
+        match i.try_into() {
+            Ok(i) => Ok(Some(Type::Integer(i))),
+            Err(_) => Err(SerError::IntegerOutOfRange),
+        }
+    }
+}
+
+// Wraps the contents of a variant in a dictionary keyed by its name.
+fn variant(name: &str, t: Type) -> Type {
+    Type::Dictionary(BTreeMap::from([(name.as_bytes().to_vec(), t)]))
+}
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = Option<Type>;
+    type Error = SerError;
+    type SerializeSeq = ListSerializer;
+    type SerializeTuple = ListSerializer;
+    type SerializeTupleStruct = ListSerializer;
+    type SerializeTupleVariant = ListSerializer;
+    type SerializeMap = DictionarySerializer;
+    type SerializeStruct = DictionarySerializer;
+    type SerializeStructVariant = DictionarySerializer;
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    fn serialize_bool(self, v: bool) -> Result<Option<Type>, SerError> {
+        ValueSerializer::integer(v as i64)
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Option<Type>, SerError> {
+        ValueSerializer::integer(v)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Option<Type>, SerError> {
+        ValueSerializer::integer(v)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Option<Type>, SerError> {
+        ValueSerializer::integer(v)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Option<Type>, SerError> {
+        ValueSerializer::integer(v)
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Option<Type>, SerError> {
+        ValueSerializer::integer(v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Option<Type>, SerError> {
+        ValueSerializer::integer(v)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Option<Type>, SerError> {
+        ValueSerializer::integer(v)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Option<Type>, SerError> {
+        ValueSerializer::integer(v)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Option<Type>, SerError> {
+        ValueSerializer::integer(v)
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Option<Type>, SerError> {
+        ValueSerializer::integer(v)
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<Option<Type>, SerError> {
+        Err(SerError::UnsupportedType("f32"))
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<Option<Type>, SerError> {
+        Err(SerError::UnsupportedType("f64"))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Option<Type>, SerError> {
+        Ok(Some(Type::ByteString(v.to_string().into_bytes())))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Option<Type>, SerError> {
+        Ok(Some(Type::ByteString(v.as_bytes().to_vec())))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Option<Type>, SerError> {
+        match self.raw {
+            true => match decode_slice(v, &DecodeOptions::default()) {
+                Ok(t) => Ok(Some(t)),
+                Err(err) => Err(SerError::Custom(err.to_string())),
+            },
+            false => Ok(Some(Type::ByteString(v.to_vec()))),
+        }
+    }
+
+    fn serialize_none(self) -> Result<Option<Type>, SerError> {
+        match self.skip_none {
+            true => Ok(None),
+            false => Err(SerError::UnsupportedNone),
+        }
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Option<Type>, SerError>
+    where
+        T: Serialize + ?Sized
+    {
+        value.serialize(ValueSerializer { skip_none: false, raw: self.raw })
+    }
+
+    fn serialize_unit(self) -> Result<Option<Type>, SerError> {
+        Err(SerError::UnsupportedType("unit"))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Option<Type>, SerError> {
+        Err(SerError::UnsupportedType("unit struct"))
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str) -> Result<Option<Type>, SerError> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<Option<Type>, SerError>
+    where
+        T: Serialize + ?Sized
+    {
+        value.serialize(ValueSerializer { skip_none: false, raw: name == raw::TOKEN })
+    }
+
+    fn serialize_newtype_variant<T>(self, _name: &'static str, _index: u32, name: &'static str, value: &T) -> Result<Option<Type>, SerError>
+    where
+        T: Serialize + ?Sized
+    {
+        Ok(Some(variant(name, to_value(value)?)))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<ListSerializer, SerError> {
+        Ok(ListSerializer { items: Vec::with_capacity(len.unwrap_or(0)), variant: None })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<ListSerializer, SerError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<ListSerializer, SerError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(self, _name: &'static str, _index: u32, name: &'static str, len: usize) -> Result<ListSerializer, SerError> {
+        Ok(ListSerializer { items: Vec::with_capacity(len), variant: Some(name) })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<DictionarySerializer, SerError> {
+        Ok(DictionarySerializer { d: BTreeMap::new(), key: None, variant: None })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<DictionarySerializer, SerError> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(self, _name: &'static str, _index: u32, name: &'static str, _len: usize) -> Result<DictionarySerializer, SerError> {
+        Ok(DictionarySerializer { d: BTreeMap::new(), key: None, variant: Some(name) })
+    }
+}
+
+#[doc(hidden)]
+pub struct ListSerializer {
+    items: Vec<Type>,
+    variant: Option<&'static str>,
+}
+
+impl ListSerializer {
+    fn finish(self) -> Result<Option<Type>, SerError> {
+        let t = Type::List(self.items);
+
+        match self.variant {
+            Some(name) => Ok(Some(variant(name, t))),
+            None => Ok(Some(t)),
+        }
+    }
+}
+
+impl ser::SerializeSeq for ListSerializer {
+    type Ok = Option<Type>;
+    type Error = SerError;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), SerError>
+    where
+        T: Serialize + ?Sized
+    {
+        self.items.push(to_value(value)?);
+
+        Ok(())
+    }
+
+    fn end(self) -> Result<Option<Type>, SerError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for ListSerializer {
+    type Ok = Option<Type>;
+    type Error = SerError;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), SerError>
+    where
+        T: Serialize + ?Sized
+    {
+        self.items.push(to_value(value)?);
+
+        Ok(())
+    }
+
+    fn end(self) -> Result<Option<Type>, SerError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for ListSerializer {
+    type Ok = Option<Type>;
+    type Error = SerError;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), SerError>
+    where
+        T: Serialize + ?Sized
+    {
+        self.items.push(to_value(value)?);
+
+        Ok(())
+    }
+
+    fn end(self) -> Result<Option<Type>, SerError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for ListSerializer {
+    type Ok = Option<Type>;
+    type Error = SerError;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), SerError>
+    where
+        T: Serialize + ?Sized
+    {
+        self.items.push(to_value(value)?);
+
+        Ok(())
+    }
+
+    fn end(self) -> Result<Option<Type>, SerError> {
+        self.finish()
+    }
+}
+
+#[doc(hidden)]
+pub struct DictionarySerializer {
+    d: BTreeMap<Vec<u8>, Type>,
+    key: Option<Vec<u8>>,
+    variant: Option<&'static str>,
+}
+
+impl DictionarySerializer {
+    fn entry<T>(&mut self, key: Vec<u8>, value: &T) -> Result<(), SerError>
+    where
+        T: Serialize + ?Sized
+    {
+        let t = match value.serialize(ValueSerializer { skip_none: true, raw: false })? {
+            Some(t) => t,
+            None => return Ok(()),
+        };
+
+        match self.d.insert(key, t) {
+            Some(_) => Err(SerError::DuplicateKey),
+            None => Ok(()),
+        }
+    }
+
+    fn finish(self) -> Result<Option<Type>, SerError> {
+        let t = Type::Dictionary(self.d);
+
+        match self.variant {
+            Some(name) => Ok(Some(variant(name, t))),
+            None => Ok(Some(t)),
+        }
+    }
+}
+
+impl ser::SerializeMap for DictionarySerializer {
+    type Ok = Option<Type>;
+    type Error = SerError;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), SerError>
+    where
+        T: Serialize + ?Sized
+    {
+        self.key = Some(key.serialize(KeySerializer)?);
+
+        Ok(())
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), SerError>
+    where
+        T: Serialize + ?Sized
+    {
+        match self.key.take() {
+            Some(key) => self.entry(key, value),
+            None => Err(SerError::Custom("map value serialized before its key".to_string())),
+        }
+    }
+
+    fn end(self) -> Result<Option<Type>, SerError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStruct for DictionarySerializer {
+    type Ok = Option<Type>;
+    type Error = SerError;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), SerError>
+    where
+        T: Serialize + ?Sized
+    {
+        self.entry(key.as_bytes().to_vec(), value)
+    }
+
+    fn end(self) -> Result<Option<Type>, SerError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for DictionarySerializer {
+    type Ok = Option<Type>;
+    type Error = SerError;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), SerError>
+    where
+        T: Serialize + ?Sized
+    {
+        self.entry(key.as_bytes().to_vec(), value)
+    }
+
+    fn end(self) -> Result<Option<Type>, SerError> {
+        self.finish()
+    }
+}
+
+enum Segment<'a> {
+    Key(&'a [u8]),
+    Index(usize),
+}
+
+// Adds the segment of an enclosing list item or dictionary value to the
+// front of an error's path, which gets built this way on the way out.
+fn within(err: ConverterError, segment: Segment) -> ConverterError {
+    let (message, path) = match err {
+        ConverterError::Custom { message, path } => (message, path),
+        err => return err,
+    };
+
+    let mut prefix = match segment {
+        Segment::Key(key) => String::from_utf8_lossy(key).into_owned(),
+        Segment::Index(i) => format!("[{}]", i),
+    };
+    if !path.is_empty() && !path.starts_with('[') {
+        prefix.push('.');
+    }
+    prefix.push_str(&path);
+
+    ConverterError::Custom { message, path: prefix }
+}
+
+struct ValueDeserializer {
+    value: Type,
+}
+
+impl ValueDeserializer {
+    fn value<'de, V>(self, visitor: V, text: bool) -> Result<V::Value, ConverterError>
+    where
+        V: Visitor<'de>
+    {
+        let mut value = self.value;
+        match &mut value {
+            Type::Integer(i) => visitor.visit_i64(*i),
+            Type::ByteString(bytes) if text => mem::take(bytes).visit_str(visitor),
+            Type::ByteString(bytes) => mem::take(bytes).visit_bytes(visitor),
+            Type::List(l) => {
+                let len = l.len();
+                let mut access = ListAccess { items: mem::take(l).into_iter(), index: 0 };
+                let v = visitor.visit_seq(&mut access)?;
+                match access.items.len() {
+                    0 => Ok(v),
+                    _ => Err(de::Error::invalid_length(len, &"fewer items")),
+                }
+            },
+            Type::Dictionary(d) => {
+                let len = d.len();
+                let mut access = DictionaryAccess { entries: mem::take(d).into_iter(), value: None };
+                let v = visitor.visit_map(&mut access)?;
+                match access.entries.len() {
+                    0 => Ok(v),
+                    _ => Err(de::Error::invalid_length(len, &"fewer entries")),
+                }
+            },
+        }
+    }
+}
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer {
+    type Error = ConverterError;
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, ConverterError>
+    where
+        V: Visitor<'de>
+    {
+        self.value(visitor, false)
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value, ConverterError>
+    where
+        V: Visitor<'de>
+    {
+        match self.value {
+            Type::Integer(0) => visitor.visit_bool(false),
+            Type::Integer(1) => visitor.visit_bool(true),
+            Type::Integer(i) => Err(de::Error::invalid_value(Unexpected::Signed(i), &"0 or 1")),
+            _ => self.value(visitor, false),
+        }
+    }
+
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, ConverterError>
+    where
+        V: Visitor<'de>
+    {
+        self.value(visitor, true)
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, ConverterError>
+    where
+        V: Visitor<'de>
+    {
+        self.value(visitor, true)
+    }
+
+    fn deserialize_char<V>(self, visitor: V) -> Result<V::Value, ConverterError>
+    where
+        V: Visitor<'de>
+    {
+        self.value(visitor, true)
+    }
+
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value, ConverterError>
+    where
+        V: Visitor<'de>
+    {
+        self.value(visitor, true)
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, ConverterError>
+    where
+        V: Visitor<'de>
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value, ConverterError>
+    where
+        V: Visitor<'de>
+    {
+        match name {
+            raw::TOKEN => visitor.visit_byte_buf(encode(&self.value)),
+            _ => visitor.visit_newtype_struct(self),
+        }
+    }
+
+    fn deserialize_enum<V>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V) -> Result<V::Value, ConverterError>
+    where
+        V: Visitor<'de>
+    {
+        let mut value = self.value;
+        match &mut value {
+            Type::ByteString(name) => visitor.visit_enum(UnitVariant::new(mem::take(name))),
+            Type::Dictionary(d) if d.len() == 1 => {
+                let (name, value) = match mem::take(d).into_iter().next() {
+                    Some(entry) => entry,
+                    None => unreachable!("the dictionary has one entry"),
+                };
+                visitor.visit_enum(VariantAccess { name, value })
+            },
+            _ => ValueDeserializer { value }.value(visitor, false),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 bytes byte_buf unit
+        unit_struct seq tuple tuple_struct map struct ignored_any
+    }
+}
+
+struct ListAccess {
+    items: std::vec::IntoIter<Type>,
+    index: usize,
+}
+
+impl<'de> de::SeqAccess<'de> for ListAccess {
+    type Error = ConverterError;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, ConverterError>
+    where
+        T: DeserializeSeed<'de>
+    {
+        let value = match self.items.next() {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+        let index = self.index;
+        self.index += 1;
+
+        match seed.deserialize(ValueDeserializer { value }) {
+            Ok(t) => Ok(Some(t)),
+            Err(err) => Err(within(err, Segment::Index(index))),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.items.len())
+    }
+}
+
+struct DictionaryAccess {
+    entries: std::collections::btree_map::IntoIter<Vec<u8>, Type>,
+    // The entry whose key was handed out and whose value is next.
+    value: Option<(Vec<u8>, Type)>,
+}
+
+impl<'de> de::MapAccess<'de> for DictionaryAccess {
+    type Error = ConverterError;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, ConverterError>
+    where
+        K: DeserializeSeed<'de>
+    {
+        let (key, value) = match self.entries.next() {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+
+        let res = seed.deserialize(KeyDeserializer::new(key.clone()));
+        self.value = Some((key, value));
+
+        res.map(Some)
+    }
+
+    fn next_value_seed<T>(&mut self, seed: T) -> Result<T::Value, ConverterError>
+    where
+        T: DeserializeSeed<'de>
+    {
+        let (key, value) = match self.value.take() {
+            Some(entry) => entry,
+            None => return Err(de::Error::custom("value asked for before its key")),
+        };
+
+        seed.deserialize(ValueDeserializer { value }).map_err(|err| within(err, Segment::Key(&key)))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.entries.len())
+    }
+}
+
+// A variant other than a unit one, held as the only entry of a dictionary.
+struct VariantAccess {
+    name: Vec<u8>,
+    value: Type,
+}
+
+impl<'de> de::EnumAccess<'de> for VariantAccess {
+    type Error = ConverterError;
+    type Variant = Self;
+
+    fn variant_seed<T>(self, seed: T) -> Result<(T::Value, Self), ConverterError>
+    where
+        T: DeserializeSeed<'de>
+    {
+        let variant = seed.deserialize(KeyDeserializer::new(self.name.clone()))?;
+
+        Ok((variant, self))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for VariantAccess {
+    type Error = ConverterError;
+
+    fn unit_variant(self) -> Result<(), ConverterError> {
+        Err(de::Error::invalid_type(Unexpected::NewtypeVariant, &"unit variant"))
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, ConverterError>
+    where
+        T: DeserializeSeed<'de>
+    {
+        let name = self.name;
+        seed.deserialize(ValueDeserializer { value: self.value }).map_err(|err| within(err, Segment::Key(&name)))
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value, ConverterError>
+    where
+        V: Visitor<'de>
+    {
+        let name = self.name;
+        de::Deserializer::deserialize_seq(ValueDeserializer { value: self.value }, visitor).map_err(|err| within(err, Segment::Key(&name)))
+    }
+
+    fn struct_variant<V>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value, ConverterError>
+    where
+        V: Visitor<'de>
+    {
+        let name = self.name;
+        de::Deserializer::deserialize_map(ValueDeserializer { value: self.value }, visitor).map_err(|err| within(err, Segment::Key(&name)))
+    }
+}
@@ -0,0 +1,37 @@
+use std::fmt;
+
+
+/// A deviation from the bencode specification that was accepted while
+/// decoding in [`DecodeMode::Lenient`](crate::DecodeMode::Lenient).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecodeWarning {
+    pub kind: DecodeWarningKind,
+    pub offset: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecodeWarningKind {
+    IntegerWithLeadingZeros,
+    ByteStringLengthWithLeadingZeros,
+    UnsortedDictionaryKeys,
+    DuplicateDictionaryKey,
+    TrailingBytes,
+}
+
+impl fmt::Display for DecodeWarningKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeWarningKind::IntegerWithLeadingZeros => write!(f, "integer with leading zeros"),
+            DecodeWarningKind::ByteStringLengthWithLeadingZeros => write!(f, "byte string length with leading zeros"),
+            DecodeWarningKind::UnsortedDictionaryKeys => write!(f, "dictionary keys are not sorted"),
+            DecodeWarningKind::DuplicateDictionaryKey => write!(f, "duplicate dictionary key"),
+            DecodeWarningKind::TrailingBytes => write!(f, "trailing bytes after value"),
+        }
+    }
+}
+
+impl fmt::Display for DecodeWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at byte {}", self.kind, self.offset)
+    }
+}